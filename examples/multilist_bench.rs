@@ -92,7 +92,7 @@ fn gen_elem_and_index(rng: &mut Xoshiro256Plus, ml: &MultiList<Item>) -> (Item,
     (rng.gen_range(0..10), Index::new(level, node))
 }
 
-fn print_stats(measures: &Vec<Duration>) {
+fn print_stats(measures: &[Duration]) {
     let min = measures.iter().min().unwrap();
     let avg = measures.iter().sum::<Duration>() / measures.len() as u32;
     let max = measures.iter().max().unwrap();
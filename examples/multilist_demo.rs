@@ -1,3 +1,4 @@
+use advanced_structures::error::MultiListError;
 use advanced_structures::lists::multi_list::Index;
 use advanced_structures::lists::MultiList;
 use std::error::Error;
@@ -120,7 +121,12 @@ fn attach_child(ml: &mut MultiList<Item>, args: Vec<&str>) -> Result<(), Box<dyn
     check_args(3, args.len(), None)?;
 
     let at = parse_index(&args, 1)?;
-    ml.attach_child(at, args[2].parse()?)?;
+    match ml.attach_child(at, args[2].parse()?) {
+        Err(MultiListError::ChildExists) => {
+            return Err("node already has a child, use insert to add to it".into())
+        }
+        result => result?,
+    }
 
     info(ml);
     Ok(())
@@ -174,7 +180,7 @@ fn info(ml: &MultiList<Item>) {
     println!("Size: {}  Levels: {}", ml.size(), ml.levels());
 }
 
-fn show(copies: &mut Vec<MultiList<Item>>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn show(copies: &[MultiList<Item>], args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     match args.len() {
         1 => {
             info(copies.last().unwrap());
@@ -1,4 +1,4 @@
-use advanced_structures::lists::SkipList;
+use advanced_structures::lists::{Arena, ArenaSkipList, SkipList};
 use clap::Parser;
 use rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro256Plus;
@@ -8,8 +8,82 @@ use std::io::{self, Write};
 use std::path::Path;
 use std::time::{Duration, Instant};
 
+type Key = usize;
 type Item = i32;
-type BenchFn = fn(usize, usize, f64) -> (Vec<Duration>, SkipList<Item>);
+
+/// Common surface of the pointer-based and the arena-based skip lists
+trait BenchList: Sized {
+    const LAYOUT: &'static str;
+
+    fn generate(size: usize, fraction: f64) -> Self;
+    fn insert(&mut self, key: Key, value: Item) -> bool;
+    fn pop(&mut self, key: &Key) -> Option<Item>;
+    fn find(&self, key: &Key) -> Option<&Item>;
+    fn node_ptrs(&self) -> usize;
+    fn cur_level(&self) -> usize;
+}
+
+impl BenchList for SkipList<Key, Item> {
+    const LAYOUT: &'static str = "pointer";
+
+    fn generate(size: usize, fraction: f64) -> Self {
+        let mut sl = SkipList::with_fraction(fraction);
+        sl.reseed(42);
+        fill(size, |key, value| sl.insert(key, value).is_ok());
+        sl
+    }
+
+    fn insert(&mut self, key: Key, value: Item) -> bool {
+        self.insert(key, value).is_ok()
+    }
+
+    fn pop(&mut self, key: &Key) -> Option<Item> {
+        self.pop(key)
+    }
+
+    fn find(&self, key: &Key) -> Option<&Item> {
+        self.find(key)
+    }
+
+    fn node_ptrs(&self) -> usize {
+        self.node_ptrs()
+    }
+
+    fn cur_level(&self) -> usize {
+        self.cur_level()
+    }
+}
+
+impl BenchList for ArenaSkipList<Key, Item> {
+    const LAYOUT: &'static str = "arena";
+
+    fn generate(size: usize, fraction: f64) -> Self {
+        let mut sl = ArenaSkipList::with_capacity_in(0, fraction, Arena::new());
+        sl.reseed(42);
+        fill(size, |key, value| sl.insert(key, value).is_ok());
+        sl
+    }
+
+    fn insert(&mut self, key: Key, value: Item) -> bool {
+        self.insert(key, value).is_ok()
+    }
+
+    fn pop(&mut self, key: &Key) -> Option<Item> {
+        self.pop(key)
+    }
+
+    fn find(&self, key: &Key) -> Option<&Item> {
+        self.find(key)
+    }
+
+    fn node_ptrs(&self) -> usize {
+        self.node_ptrs()
+    }
+
+    fn cur_level(&self) -> usize {
+        self.cur_level()
+    }
+}
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -17,6 +91,11 @@ struct Args {
     #[arg(value_parser = clap::builder::PossibleValuesParser::new(["insert", "pop", "find"]))]
     method: String,
 
+    /// Node layout to benchmark
+    #[arg(short = 'l', default_value = "both")]
+    #[arg(value_parser = clap::builder::PossibleValuesParser::new(["pointer", "arena", "both"]))]
+    layout: String,
+
     /// How many results
     #[arg(short = 'r')]
     runs: usize,
@@ -32,20 +111,27 @@ struct Args {
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse() as Args;
+
+    if args.layout != "arena" {
+        bench::<SkipList<Key, Item>>(&args)?;
+    }
+    if args.layout != "pointer" {
+        bench::<ArenaSkipList<Key, Item>>(&args)?;
+    }
+
+    Ok(())
+}
+
+fn bench<L: BenchList>(args: &Args) -> io::Result<()> {
     let func = match args.method.as_str() {
-        "insert" => bench_insert,
-        "pop" => bench_pop,
-        "find" => bench_find,
+        "insert" => bench_insert::<L>,
+        "pop" => bench_pop::<L>,
+        "find" => bench_find::<L>,
 
         // Will never run, so this panic is harmless
         _ => panic!(),
     };
 
-    bench(func, args)?;
-    Ok(())
-}
-
-fn bench(func: BenchFn, args: Args) -> io::Result<()> {
     let (measures_1_2, sl1) = func(args.size, args.runs, 0.5);
     let (measures_1_4, sl2) = func(args.size, args.runs, 0.25);
     let (measures_1_e, sl3) = func(args.size, args.runs, 1.0 / std::f64::consts::E);
@@ -54,13 +140,19 @@ fn bench(func: BenchFn, args: Args) -> io::Result<()> {
     print_stats("p = 1/4", &measures_1_4, &sl2);
     print_stats("p = 1/e", &measures_1_e, &sl3);
 
-    save_measures(args.output.clone(), "p=1_2.csv".into(), measures_1_2)?;
-    save_measures(args.output.clone(), "p=1_4.csv".into(), measures_1_4)?;
-    save_measures(args.output, "p=1_e.csv".into(), measures_1_e)
+    // Pointer layout results keep their original file names
+    let prefix = match L::LAYOUT {
+        "pointer" => String::new(),
+        layout => format!("{layout}_"),
+    };
+
+    save_measures(&args.output, format!("{prefix}p=1_2.csv"), measures_1_2)?;
+    save_measures(&args.output, format!("{prefix}p=1_4.csv"), measures_1_4)?;
+    save_measures(&args.output, format!("{prefix}p=1_e.csv"), measures_1_e)
 }
 
-fn save_measures(dir: String, filename: String, measures: Vec<Duration>) -> io::Result<()> {
-    let dir_path = Path::new(&dir);
+fn save_measures(dir: &str, filename: String, measures: Vec<Duration>) -> io::Result<()> {
+    let dir_path = Path::new(dir);
     dir_path.try_exists()?;
 
     let mut file = File::create(dir_path.join(filename))?;
@@ -73,24 +165,25 @@ fn save_measures(dir: String, filename: String, measures: Vec<Duration>) -> io::
     file.write_all(buf.as_bytes())
 }
 
-fn generate_skip_list(size: usize, fraction: f64) -> SkipList<Item> {
+/// Feeds `insert` with random pairs until `size` of them succeed.
+fn fill<F: FnMut(Key, Item) -> bool>(size: usize, mut insert: F) {
     let mut rng = Xoshiro256Plus::seed_from_u64(42);
-    let mut sl = SkipList::<Item>::with_fraction(fraction);
+    let mut len = 0;
 
-    while sl.len() < size {
+    while len < size {
         let (key, value) = gen_key_value(&mut rng);
-        sl.insert(key, value).unwrap_or(());
+        if insert(key, value) {
+            len += 1;
+        }
     }
-
-    sl
 }
 
-fn gen_key_value(rng: &mut Xoshiro256Plus) -> (usize, Item) {
+fn gen_key_value(rng: &mut Xoshiro256Plus) -> (Key, Item) {
     (rng.gen_range(0..usize::MAX), rng.gen())
 }
 
-fn bench_insert(size: usize, runs: usize, fraction: f64) -> (Vec<Duration>, SkipList<Item>) {
-    let (mut rng, mut measures, mut sl) = set_up(size, fraction);
+fn bench_insert<L: BenchList>(size: usize, runs: usize, fraction: f64) -> (Vec<Duration>, L) {
+    let (mut rng, mut measures, mut sl) = set_up::<L>(size, fraction);
 
     while measures.len() < runs {
         let (key, value) = gen_key_value(&mut rng);
@@ -99,61 +192,61 @@ fn bench_insert(size: usize, runs: usize, fraction: f64) -> (Vec<Duration>, Skip
         let res = sl.insert(key, value);
         let elapsed = now.elapsed();
 
-        if res.is_ok() {
+        if res {
             measures.push(elapsed);
-            sl.pop(key).unwrap_or_default();
+            sl.pop(&key).unwrap_or_default();
         }
     }
 
     (measures, sl)
 }
 
-fn bench_pop(size: usize, runs: usize, fraction: f64) -> (Vec<Duration>, SkipList<Item>) {
-    let (mut rng, mut measures, mut sl) = set_up(size, fraction);
+fn bench_pop<L: BenchList>(size: usize, runs: usize, fraction: f64) -> (Vec<Duration>, L) {
+    let (mut rng, mut measures, mut sl) = set_up::<L>(size, fraction);
 
     while measures.len() < runs {
         let (key, _) = gen_key_value(&mut rng);
 
         let now = Instant::now();
-        let res = sl.pop(key);
+        let res = sl.pop(&key);
         measures.push(now.elapsed());
 
         if let Some(value) = res {
-            sl.insert(key, value).unwrap_or(());
+            sl.insert(key, value);
         }
     }
 
     (measures, sl)
 }
 
-fn bench_find(size: usize, runs: usize, fraction: f64) -> (Vec<Duration>, SkipList<Item>) {
-    let (mut rng, mut measures, sl) = set_up(size, fraction);
+fn bench_find<L: BenchList>(size: usize, runs: usize, fraction: f64) -> (Vec<Duration>, L) {
+    let (mut rng, mut measures, sl) = set_up::<L>(size, fraction);
 
     while measures.len() < runs {
         let (key, _) = gen_key_value(&mut rng);
         let now = Instant::now();
-        let _ = sl.find(key);
+        let _ = sl.find(&key);
         measures.push(now.elapsed())
     }
 
     (measures, sl)
 }
 
-fn set_up(size: usize, fraction: f64) -> (Xoshiro256Plus, Vec<Duration>, SkipList<Item>) {
+fn set_up<L: BenchList>(size: usize, fraction: f64) -> (Xoshiro256Plus, Vec<Duration>, L) {
     (
         Xoshiro256Plus::seed_from_u64(9857),
         Vec::new(),
-        generate_skip_list(size, fraction),
+        L::generate(size, fraction),
     )
 }
 
-fn print_stats(caption: &str, measures: &Vec<Duration>, sl: &SkipList<Item>) {
+fn print_stats<L: BenchList>(caption: &str, measures: &[Duration], sl: &L) {
     let min = measures.iter().min().unwrap();
     let avg = measures.iter().sum::<Duration>() / measures.len() as u32;
     let max = measures.iter().max().unwrap();
     let ptrs = sl.node_ptrs() as f64 / (sl.cur_level() + 1) as f64;
 
-    println!("{caption}");
+    println!("{caption} ({} layout)", L::LAYOUT);
     println!("Min: {min:?}\nMax: {max:?}\nAvg: {avg:?}");
     println!("Avg pointers per level: {ptrs:.2}\n");
 }
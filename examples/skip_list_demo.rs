@@ -1,15 +1,18 @@
+use advanced_structures::error::SkipListError;
 use advanced_structures::lists::skip_list::SkipList;
 use rand::{thread_rng, Rng};
 use std::error::Error;
+use std::fs::File;
 use std::io::stdin;
 
+type Key = usize;
 type Item = i32;
 
 fn main() {
     print_help();
     print_breaks();
 
-    let mut copies: Vec<SkipList<Item>> = Vec::new();
+    let mut copies: Vec<SkipList<Key, Item>> = Vec::new();
     copies.push(default().unwrap());
 
     loop {
@@ -47,18 +50,23 @@ fn print_help() {
     println!("\t{:<42}Delete elem from skip list via key", "pop [key]");
     println!("\t{:<42}Find elem by [key]", "find [key]");
     println!("\t{:<42}Clear the list", "clear");
+    println!("\t{:<42}Show level histogram and search cost", "stats");
+    println!(
+        "\t{:<42}Save tower layout as Graphviz to [file]",
+        "dot [file]"
+    );
     println!("\t{:<42}Create a copy of multilist", "clone");
     println!(
         "\t{:<42}Create new list with [max_level] & [fraction],",
         "new [fraction] [max_level]"
     );
     println!("\t{:<42}[max_level] can be 0 (unlimited),", "");
-    println!("\t{:<42}[fraction] must be from 0 to 1, or random", "");
+    println!("\t{:<42}[fraction] must be between 0 and 1, or random", "");
     println!("\t{:<42}Set a copy [N] as current list", "restore [N]");
     println!("\t{:<42}Exit the program", "exit");
 }
 
-fn parse_args(buf: String, copies: &mut Vec<SkipList<Item>>) -> Result<bool, Box<dyn Error>> {
+fn parse_args(buf: String, copies: &mut Vec<SkipList<Key, Item>>) -> Result<bool, Box<dyn Error>> {
     let args: Vec<&str> = buf.split(' ').collect();
     let cur_ml_index = copies.len() - 1;
     let sl = copies.get_mut(cur_ml_index).unwrap();
@@ -70,6 +78,8 @@ fn parse_args(buf: String, copies: &mut Vec<SkipList<Item>>) -> Result<bool, Box
         "pop" => pop(sl, args)?,
         "find" => find(sl, args)?,
         "clear" => sl.clear(),
+        "stats" => stats(sl),
+        "dot" => dot(sl, args)?,
         "clone" => {
             let copy = sl.clone();
             copies.push(copy)
@@ -83,23 +93,23 @@ fn parse_args(buf: String, copies: &mut Vec<SkipList<Item>>) -> Result<bool, Box
     Ok(false)
 }
 
-fn new(copies: &mut Vec<SkipList<Item>>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn new(copies: &mut Vec<SkipList<Key, Item>>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     check_args(3, args.len(), None)?;
 
     let fraction = if args[1] == "random" {
-        thread_rng().gen_range(0.0..1.0)
+        thread_rng().gen_range(0.05..0.95)
     } else {
         match args[1].parse::<f64>() {
-            Ok(f) => f,
-            Err(_) => return Err("incorrect fraction".into()),
+            Ok(f) if f > 0.0 && f < 1.0 => f,
+            _ => return Err("incorrect fraction".into()),
         }
     };
 
     let max_level = if let Ok(n) = args[2].parse::<usize>() {
-        if n == 0 {
-            u16::MAX as usize
-        } else {
-            n
+        match n {
+            0 => u16::MAX as usize,
+            n if n <= u16::MAX as usize => n,
+            _ => return Err("incorrect max level".into()),
         }
     } else {
         return Err("incorrect max level".into());
@@ -109,17 +119,24 @@ fn new(copies: &mut Vec<SkipList<Item>>, args: Vec<&str>) -> Result<(), Box<dyn
     Ok(())
 }
 
-fn insert(sl: &mut SkipList<Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn insert(sl: &mut SkipList<Key, Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     check_args(3, args.len(), None)?;
-    sl.insert(args[1].parse()?, args[2].parse()?)?;
-    info(sl);
-    Ok(())
+    let key = args[1].parse()?;
+
+    match sl.insert(key, args[2].parse()?) {
+        Err(SkipListError::DuplicateKey) => Err(format!("key {key} already exists").into()),
+        result => {
+            result?;
+            info(sl);
+            Ok(())
+        }
+    }
 }
 
-fn pop(sl: &mut SkipList<Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn pop(sl: &mut SkipList<Key, Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     check_args(2, args.len(), None)?;
 
-    match sl.pop(args[1].parse()?) {
+    match sl.pop(&args[1].parse()?) {
         None => Err("key not found".into()),
         Some(_) => {
             info(sl);
@@ -128,10 +145,10 @@ fn pop(sl: &mut SkipList<Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     }
 }
 
-fn find(sl: &SkipList<Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn find(sl: &SkipList<Key, Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     check_args(2, args.len(), None)?;
 
-    match sl.find(args[1].parse()?) {
+    match sl.find(&args[1].parse()?) {
         None => Err("key not found".into()),
         Some(found) => {
             println!("Found {found}");
@@ -140,6 +157,24 @@ fn find(sl: &SkipList<Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     }
 }
 
+fn stats(sl: &SkipList<Key, Item>) {
+    let stats = sl.stats();
+    for (lvl, count) in stats.levels.iter().enumerate().rev() {
+        println!("Lv{lvl} - {count} node(s)");
+    }
+    println!(
+        "Avg search path: {:.2}  Memory: {} bytes  Pointers: {}",
+        stats.avg_search_path, stats.memory, stats.pointers
+    );
+}
+
+fn dot(sl: &SkipList<Key, Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+    check_args(2, args.len(), None)?;
+    sl.write_dot(&mut File::create(args[1])?)?;
+    println!("Saved to {}", args[1]);
+    Ok(())
+}
+
 fn check_args(expected: usize, actual: usize, msg: Option<&str>) -> Result<(), &str> {
     if expected == actual {
         Ok(())
@@ -148,7 +183,7 @@ fn check_args(expected: usize, actual: usize, msg: Option<&str>) -> Result<(), &
     }
 }
 
-fn info(sl: &SkipList<Item>) {
+fn info(sl: &SkipList<Key, Item>) {
     print!("{sl:?}");
     println!(
         "Length: {}  Current level: {}  Max level: {}  Pointers: {}",
@@ -159,7 +194,7 @@ fn info(sl: &SkipList<Item>) {
     );
 }
 
-fn show(copies: &mut Vec<SkipList<Item>>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn show(copies: &[SkipList<Key, Item>], args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     match args.len() {
         1 => {
             info(copies.last().unwrap());
@@ -180,7 +215,7 @@ fn show(copies: &mut Vec<SkipList<Item>>, args: Vec<&str>) -> Result<(), Box<dyn
     }
 }
 
-fn restore(copies: &mut Vec<SkipList<Item>>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn restore(copies: &mut Vec<SkipList<Key, Item>>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     check_args(2, args.len(), None)?;
 
     match (copies.len() - 1).checked_sub(args[1].parse::<usize>()?) {
@@ -196,8 +231,8 @@ fn restore(copies: &mut Vec<SkipList<Item>>, args: Vec<&str>) -> Result<(), Box<
     }
 }
 
-fn default() -> Result<SkipList<Item>, Box<dyn Error>> {
-    let mut sl = SkipList::<Item>::default();
+fn default() -> Result<SkipList<Key, Item>, Box<dyn Error>> {
+    let mut sl = SkipList::<Key, Item>::default();
 
     sl.insert(3, 100)?;
     sl.insert(6, 50)?;
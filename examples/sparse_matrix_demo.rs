@@ -87,7 +87,7 @@ fn mul(m: &mut SparseMatrix<Item>, args: Vec<&str>) -> Result<(), Box<dyn Error>
     Ok(())
 }
 
-fn add(copies: &mut Vec<SparseMatrix<Item>>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn add(copies: &mut [SparseMatrix<Item>], args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     check_args(2, args.len(), None)?;
 
     let m1 = copies.last().unwrap().clone();
@@ -188,7 +188,7 @@ fn info(m: &SparseMatrix<Item>) {
     print!("{m:?}");
 }
 
-fn show(copies: &mut Vec<SparseMatrix<Item>>, args: Vec<&str>) -> Result<(), Box<dyn Error>> {
+fn show(copies: &[SparseMatrix<Item>], args: Vec<&str>) -> Result<(), Box<dyn Error>> {
     match args.len() {
         1 => {
             info(copies.last().unwrap());
@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Error returned by the fallible methods of `SkipList` and `ArenaSkipList`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipListError {
+    /// The key is already in the list, which was left unchanged.
+    DuplicateKey,
+    /// The key does not fit between the entries around a cursor,
+    /// or a sorted batch is not in ascending order.
+    UnorderedKey,
+}
+
+impl Display for SkipListError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipListError::DuplicateKey => write!(f, "provided key already exists"),
+            SkipListError::UnorderedKey => write!(f, "key is out of order"),
+        }
+    }
+}
+
+impl Error for SkipListError {}
+
+/// Error returned by the fallible methods of `MultiList`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiListError {
+    /// No node or list position exists at this index.
+    IndexOutOfBounds { level: usize, node: usize },
+    /// `insert_alt` needs a node to insert after, so the node index can't be 0.
+    ZeroNodeIndex,
+    /// The node already has a child list.
+    ChildExists,
+    /// The multilist has fewer levels than that.
+    LevelOutOfBounds { level: usize },
+}
+
+impl Display for MultiListError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MultiListError::IndexOutOfBounds { level, node } => {
+                write!(f, "index {level},{node} is out of bounds")
+            }
+            MultiListError::ZeroNodeIndex => {
+                write!(f, "wrong local node index, should be at least 1")
+            }
+            MultiListError::ChildExists => write!(f, "child already exists"),
+            MultiListError::LevelOutOfBounds { level } => {
+                write!(f, "level {level} does not exist")
+            }
+        }
+    }
+}
+
+impl Error for MultiListError {}
@@ -1,6 +1,9 @@
 use std::ptr::NonNull;
 
+pub mod error;
 pub mod lists;
 pub mod sparse_matrix;
+#[cfg(test)]
+mod test_support;
 
 type MaybeNone<T> = Option<NonNull<T>>;
@@ -0,0 +1,250 @@
+use crate::lists::compare::Natural;
+use crate::lists::skip_list::SkipList;
+use crate::lists::storage::Nodes;
+use std::mem::size_of;
+
+/// `SkipList` that keeps its nodes in an `Arena` instead of separate
+/// allocations. The API is the same, as only the storage differs; build one
+/// with the `_in` constructors, e.g. `SkipList::new_in(0.5, 16, Arena::new())`.
+pub type ArenaSkipList<K, V, C = Natural> = SkipList<K, V, C, Arena<K, V>>;
+
+/// Storage that keeps nodes in a slab.
+///
+/// Nodes refer to each other by slot index, and every tower lives inline in
+/// one shared `links` vector. Only the head keeps a tower of its own, in the
+/// list, so it can grow in capacity mode. Removed slots are recycled by later
+/// insertions whose tower fits in them, so a list that stays about the same
+/// size rarely grows the arena.
+///
+/// Slot indices mean nothing to another arena, so `append` and `split_off`
+/// move the nodes over one by one instead of relinking whole towers.
+#[derive(Clone)]
+pub struct Arena<K, V> {
+    slots: Vec<Slot<K, V>>,
+    links: Vec<Link>,
+    /// Free slots grouped by the height of their tower.
+    free: Vec<Vec<usize>>,
+}
+
+/// Marks the end of a level in `links`.
+const NIL: usize = usize::MAX;
+
+#[derive(Clone, Copy)]
+struct Link {
+    next: usize,
+    /// How many level 0 steps the link skips.
+    span: usize,
+}
+
+const UNLINKED: Link = Link { next: NIL, span: 0 };
+
+#[derive(Clone)]
+struct Slot<K, V> {
+    tower: usize,
+    /// Highest level the tower has room for.
+    height: usize,
+    /// Highest level the node is linked on, at most `height`.
+    level: usize,
+    /// Previous node on level 0, `None` for the first one.
+    prev: Option<usize>,
+    entry: Option<(K, V)>,
+}
+
+impl<K, V> Arena<K, V> {
+    pub fn new() -> Self {
+        Arena {
+            slots: Vec::new(),
+            links: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Reaches the slot without indexing, which would borrow the whole slab
+    /// and so invalidate the values `IterMut` already handed out.
+    unsafe fn slot(&self, node: usize) -> *const Slot<K, V> {
+        self.slots.as_ptr().add(node)
+    }
+
+    unsafe fn link(&self, node: usize, lvl: usize) -> &Link {
+        &self.links[(*self.slot(node)).tower + lvl]
+    }
+
+    unsafe fn link_mut(&mut self, node: usize, lvl: usize) -> &mut Link {
+        let tower = (*self.slot(node)).tower;
+        &mut self.links[tower + lvl]
+    }
+}
+
+impl<K, V> Default for Arena<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Nodes<K, V> for Arena<K, V> {
+    type Id = usize;
+
+    const GLOBAL_IDS: bool = false;
+
+    /// Takes the lowest free tower that has room for `level`, or grows the arena.
+    fn alloc(&mut self, key: K, value: V, level: usize) -> usize {
+        let reused = self.free.iter_mut().skip(level).find_map(|free| free.pop());
+        if let Some(node) = reused {
+            let slot = &mut self.slots[node];
+            slot.level = level;
+            slot.prev = None;
+            slot.entry = Some((key, value));
+            let tower = slot.tower;
+            self.links[tower..=tower + level].fill(UNLINKED);
+            return node;
+        }
+
+        let tower = self.links.len();
+        self.links.resize(tower + level + 1, UNLINKED);
+        self.slots.push(Slot {
+            tower,
+            height: level,
+            level,
+            prev: None,
+            entry: Some((key, value)),
+        });
+        self.slots.len() - 1
+    }
+
+    /// Takes the entry out and puts the slot up for reuse.
+    unsafe fn free(&mut self, node: usize) -> (K, V) {
+        let height = self.slots[node].height;
+        if self.free.len() <= height {
+            self.free.resize(height + 1, Vec::new());
+        }
+        self.free[height].push(node);
+
+        self.slots[node].entry.take().expect("linked slot is empty")
+    }
+
+    unsafe fn adopt(&mut self, from: &mut Self, node: usize) -> usize {
+        let level = from.slots[node].level;
+        let (key, value) = from.free(node);
+        self.alloc(key, value, level)
+    }
+
+    unsafe fn clear(&mut self, _first: Option<usize>) {
+        self.slots.clear();
+        self.links.clear();
+        self.free.clear();
+    }
+
+    fn try_clone(&self) -> Option<Self>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        Some(self.clone())
+    }
+
+    unsafe fn level(&self, node: usize) -> usize {
+        (*self.slot(node)).level
+    }
+
+    /// A tower that has to grow moves to the end of `links`. The room it
+    /// leaves behind is only given back by `clear`, which is fine for
+    /// `rebalance`, the one caller.
+    unsafe fn set_level(&mut self, node: usize, level: usize) {
+        let slot = &mut self.slots[node];
+        if level > slot.height {
+            slot.tower = self.links.len();
+            slot.height = level;
+            self.links.resize(slot.tower + level + 1, UNLINKED);
+        }
+        slot.level = level;
+    }
+
+    unsafe fn next(&self, node: usize, lvl: usize) -> Option<usize> {
+        match self.link(node, lvl).next {
+            NIL => None,
+            next => Some(next),
+        }
+    }
+
+    unsafe fn set_next(&mut self, node: usize, lvl: usize, to: Option<usize>) {
+        self.link_mut(node, lvl).next = to.unwrap_or(NIL);
+    }
+
+    unsafe fn span(&self, node: usize, lvl: usize) -> usize {
+        self.link(node, lvl).span
+    }
+
+    unsafe fn span_mut(&mut self, node: usize, lvl: usize) -> &mut usize {
+        &mut self.link_mut(node, lvl).span
+    }
+
+    unsafe fn prev(&self, node: usize) -> Option<usize> {
+        (*self.slot(node)).prev
+    }
+
+    unsafe fn set_prev(&mut self, node: usize, to: Option<usize>) {
+        self.slots[node].prev = to;
+    }
+
+    unsafe fn key(&self, node: usize) -> &K {
+        let (key, _) = (*self.slot(node))
+            .entry
+            .as_ref()
+            .expect("linked slot is empty");
+        key
+    }
+
+    unsafe fn value(&self, node: usize) -> &V {
+        let (_, value) = (*self.slot(node))
+            .entry
+            .as_ref()
+            .expect("linked slot is empty");
+        value
+    }
+
+    unsafe fn entry_mut(&mut self, node: usize) -> (&K, &mut V) {
+        let slot = self.slots.as_mut_ptr().add(node);
+        let (key, value) = (*slot).entry.as_mut().expect("linked slot is empty");
+        (key, value)
+    }
+
+    unsafe fn value_mut(&mut self, node: usize) -> &mut V {
+        self.entry_mut(node).1
+    }
+
+    fn memory(&self) -> usize {
+        self.slots.capacity() * size_of::<Slot<K, V>>()
+            + self.links.capacity() * size_of::<Link>()
+            + self.free.capacity() * size_of::<Vec<usize>>()
+            + self
+                .free
+                .iter()
+                .map(|free| free.capacity() * size_of::<usize>())
+                .sum::<usize>()
+    }
+
+    unsafe fn node_memory(&self, _node: usize) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arena, ArenaSkipList};
+
+    #[test]
+    fn removed_slots_are_reused() {
+        // With a single level every free tower fits every new node
+        let mut list = ArenaSkipList::with_seed_in(0.5, 0, 1, Arena::new());
+        let churn = |list: &mut ArenaSkipList<u32, u32>, keys: std::ops::Range<u32>| {
+            list.retain(|_, _| false);
+            list.extend(keys.map(|key| (key, key)));
+            list.stats().memory
+        };
+
+        churn(&mut list, 0..32);
+        let memory = churn(&mut list, 32..64);
+        assert_eq!(churn(&mut list, 64..96), memory);
+        assert!(list.keys().copied().eq(64..96));
+    }
+}
@@ -0,0 +1,44 @@
+use std::cmp::Ordering;
+
+/// Total order used by a `SkipList` to arrange its keys.
+///
+/// Any `Fn(&K, &K) -> Ordering` closure is a comparator as well.
+pub trait Compare<K: ?Sized> {
+    fn compare(&self, a: &K, b: &K) -> Ordering;
+}
+
+/// Orders keys by their `Ord` implementation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Natural;
+
+/// Orders keys by their `Ord` implementation, greatest first.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Descending;
+
+impl<K> Compare<K> for Natural
+where
+    K: Ord + ?Sized,
+{
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+impl<K> Compare<K> for Descending
+where
+    K: Ord + ?Sized,
+{
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        b.cmp(a)
+    }
+}
+
+impl<K, F> Compare<K> for F
+where
+    K: ?Sized,
+    F: Fn(&K, &K) -> Ordering,
+{
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self(a, b)
+    }
+}
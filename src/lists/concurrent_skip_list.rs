@@ -0,0 +1,393 @@
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned, Shared};
+use rand::{thread_rng, Rng};
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+/// Lock-free ordered map that can be shared between threads.
+///
+/// Removal follows Harris' scheme: a node is first marked by tagging its own
+/// forward pointers, and any thread that later walks past a marked node helps
+/// to unlink it. Unlinked nodes are freed through epoch-based reclamation,
+/// so readers never touch freed memory. Values are handed out as clones,
+/// because other threads may still be reading a node after it is removed.
+/// Keys and values are dropped by whichever thread collects the garbage,
+/// hence the `Send + 'static` bounds.
+///
+/// Only point operations and a weakly consistent `iter` are offered.
+/// Ranges, positional access and the rest of `SkipList`'s API are not.
+pub struct ConcurrentSkipList<K, V> {
+    head: Box<[Atomic<Node<K, V>>]>,
+    fraction: f64,
+    max_level: usize,
+    cur_level: AtomicUsize,
+    len: AtomicUsize,
+}
+
+const MAX_LEVEL: usize = u16::MAX as usize;
+
+/// Number of entries the levels of `with_fraction` and `default` lists are
+/// sized for. The head is shared between threads and cannot grow later.
+const DEFAULT_CAPACITY: usize = u32::MAX as usize;
+
+/// Set in `Node::state` once the inserting thread stopped linking the node.
+const INSERTED: usize = 1;
+/// Set in `Node::state` once the removing thread unlinked the node.
+const REMOVED: usize = 2;
+
+struct Node<K, V> {
+    next: Box<[Atomic<Node<K, V>>]>,
+    state: AtomicUsize,
+    key: K,
+    value: V,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V, level: usize) -> Self {
+        Node {
+            next: (0..=level).map(|_| Atomic::null()).collect(),
+            state: AtomicUsize::new(0),
+            key,
+            value,
+        }
+    }
+
+    fn is_marked(&self, guard: &Guard) -> bool {
+        self.next[0].load(SeqCst, guard).tag() == 1
+    }
+}
+
+/// Iterator over clones of the entries in key order. It sees every entry
+/// that stays in the list for the whole walk, and may or may not see the
+/// ones added or removed in the meantime. The epoch stays pinned until the
+/// iterator is dropped, so removed nodes are not freed before that.
+pub struct Iter<'a, K, V> {
+    list: &'a ConcurrentSkipList<K, V>,
+    guard: Guard,
+    /// Node returned last, null before the first call to `next`.
+    current: *const Node<K, V>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V>
+where
+    K: Ord + Clone + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let guard = &self.guard;
+
+        loop {
+            // `current` was reached while pinned, so it is still allocated
+            let next = match unsafe { self.current.as_ref() } {
+                None => self.list.head[0].load(SeqCst, guard),
+                Some(current) => {
+                    let next = current.next[0].load(SeqCst, guard);
+                    if next.tag() == 0 {
+                        next
+                    } else {
+                        // A removed node may point at a node that is already
+                        // gone, so look for the successor from the top
+                        let succ = self.list.search(&current.key, guard).succs[0];
+                        match unsafe { succ.as_ref() } {
+                            Some(succ_ref) if succ_ref.key == current.key => {
+                                self.current = succ.as_raw();
+                                continue;
+                            }
+                            _ => succ,
+                        }
+                    }
+                }
+            };
+
+            let node = unsafe { next.as_ref() }?;
+            self.current = next.as_raw();
+            if !node.is_marked(guard) {
+                return Some((node.key.clone(), node.value.clone()));
+            }
+        }
+    }
+}
+
+/// Result of a descent: on every level, the tower that precedes the search
+/// key and the node that follows it.
+struct Position<'g, K, V> {
+    preds: Vec<&'g [Atomic<Node<K, V>>]>,
+    succs: Vec<Shared<'g, Node<K, V>>>,
+}
+
+impl<K, V> ConcurrentSkipList<K, V>
+where
+    K: Ord + Send + 'static,
+    V: Send + 'static,
+{
+    pub fn new(fraction: f64, max_level: usize) -> Self {
+        assert!(
+            fraction > 0.0 && fraction < 1.0,
+            "fraction must be between 0 and 1 exclusive, got {fraction}"
+        );
+        assert!(
+            max_level <= MAX_LEVEL,
+            "max_level must be at most {MAX_LEVEL}, got {max_level}"
+        );
+
+        ConcurrentSkipList {
+            head: (0..=max_level).map(|_| Atomic::null()).collect(),
+            fraction,
+            max_level,
+            cur_level: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn with_fraction(fraction: f64) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, fraction)
+    }
+
+    /// Sizes the levels for about `capacity` entries. Unlike `SkipList`,
+    /// the max level is fixed from then on, because the head is shared.
+    pub fn with_capacity(capacity: usize, fraction: f64) -> Self {
+        assert!(
+            fraction > 0.0 && fraction < 1.0,
+            "fraction must be between 0 and 1 exclusive, got {fraction}"
+        );
+        let max_level = ((capacity.max(1) as f64).ln() / (1.0 / fraction).ln()).ceil() as usize;
+        Self::new(fraction, max_level.min(MAX_LEVEL))
+    }
+
+    /// The count is exact only while no other thread modifies the list.
+    /// Meanwhile it may include entries whose insertion is still under way.
+    pub fn len(&self) -> usize {
+        self.len.load(SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn cur_level(&self) -> usize {
+        self.cur_level.load(SeqCst)
+    }
+
+    pub fn max_level(&self) -> usize {
+        self.max_level
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            list: self,
+            guard: epoch::pin(),
+            current: std::ptr::null(),
+        }
+    }
+
+    /// Returns `false` and drops the pair if `key` is already present.
+    pub fn insert(&self, key: K, value: V) -> bool {
+        let guard = &epoch::pin();
+        let level = self.random_level();
+        self.cur_level.fetch_max(level, SeqCst);
+
+        // Counted before the node is published, so that a pop of it can
+        // never bring the length below zero
+        self.len.fetch_add(1, SeqCst);
+
+        let mut node = Owned::new(Node::new(key, value, level));
+        let (mut pos, node) = loop {
+            let pos = self.search(&node.key, guard);
+            if self.found(&pos, &node.key, guard).is_some() {
+                self.len.fetch_sub(1, SeqCst);
+                return false;
+            }
+
+            for (i, succ) in pos.succs.iter().enumerate().take(level + 1) {
+                node.next[i].store(*succ, SeqCst);
+            }
+
+            match pos.preds[0][0].compare_exchange(pos.succs[0], node, SeqCst, SeqCst, guard) {
+                Ok(node) => break (pos, node),
+                Err(err) => node = err.new,
+            }
+        };
+
+        let node_ref = unsafe { node.deref() };
+        'levels: for lvl in 1..=level {
+            loop {
+                let next = node_ref.next[lvl].load(SeqCst, guard);
+                if next.tag() == 1 {
+                    break 'levels;
+                }
+
+                if next != pos.succs[lvl]
+                    && node_ref.next[lvl]
+                        .compare_exchange(next, pos.succs[lvl], SeqCst, SeqCst, guard)
+                        .is_err()
+                {
+                    break 'levels;
+                }
+
+                let pred = &pos.preds[lvl][lvl];
+                if pred
+                    .compare_exchange(pos.succs[lvl], node, SeqCst, SeqCst, guard)
+                    .is_ok()
+                {
+                    break;
+                }
+
+                pos = self.search(&node_ref.key, guard);
+                if pos.succs[0] != node {
+                    break 'levels;
+                }
+            }
+        }
+
+        // A remover may have finished while we were still linking levels,
+        // so those links have to be undone before the node can be freed
+        if node_ref.is_marked(guard) {
+            self.search(&node_ref.key, guard);
+        }
+        if node_ref.state.fetch_or(INSERTED, SeqCst) & REMOVED != 0 {
+            unsafe { guard.defer_destroy(node) }
+        }
+
+        true
+    }
+
+    pub fn find(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        let guard = &epoch::pin();
+        let pos = self.search(key, guard);
+        let node = self.found(&pos, key, guard)?;
+        Some(unsafe { node.deref() }.value.clone())
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        let guard = &epoch::pin();
+        let pos = self.search(key, guard);
+        self.found(&pos, key, guard).is_some()
+    }
+
+    pub fn pop(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        let guard = &epoch::pin();
+
+        loop {
+            let pos = self.search(key, guard);
+            let node = self.found(&pos, key, guard)?;
+            let node_ref = unsafe { node.deref() };
+
+            for lvl in (1..node_ref.next.len()).rev() {
+                node_ref.next[lvl].fetch_or(1, SeqCst, guard);
+            }
+
+            // Whoever marks the bottom level owns the removal
+            if node_ref.next[0].fetch_or(1, SeqCst, guard).tag() == 1 {
+                continue;
+            }
+
+            let value = node_ref.value.clone();
+            self.search(key, guard);
+            self.len.fetch_sub(1, SeqCst);
+
+            if node_ref.state.fetch_or(REMOVED, SeqCst) & INSERTED != 0 {
+                unsafe { guard.defer_destroy(node) }
+            }
+
+            return Some(value);
+        }
+    }
+
+    fn random_level(&self) -> usize {
+        let mut rng = thread_rng();
+        let mut level = 0;
+
+        while rng.gen_range(0.0..1.0) < self.fraction && level < self.max_level {
+            level += 1
+        }
+
+        level
+    }
+
+    fn found<'g>(
+        &self,
+        pos: &Position<'g, K, V>,
+        key: &K,
+        guard: &'g Guard,
+    ) -> Option<Shared<'g, Node<K, V>>> {
+        let node = unsafe { pos.succs[0].as_ref() }?;
+        if node.key == *key && !node.is_marked(guard) {
+            Some(pos.succs[0])
+        } else {
+            None
+        }
+    }
+
+    /// Descends to `key`, unlinking every marked node met on the way.
+    /// Restarts from the top whenever another thread changes a link first.
+    fn search<'g>(&'g self, key: &K, guard: &'g Guard) -> Position<'g, K, V> {
+        'retry: loop {
+            let top = self.cur_level.load(SeqCst);
+            let mut pos = Position {
+                preds: vec![&self.head[..]; top + 1],
+                succs: vec![Shared::null(); top + 1],
+            };
+
+            let mut pred = &self.head[..];
+            for lvl in (0..=top).rev() {
+                let mut curr = pred[lvl].load(SeqCst, guard);
+                if curr.tag() == 1 {
+                    continue 'retry;
+                }
+
+                while let Some(curr_ref) = unsafe { curr.as_ref() } {
+                    let succ = curr_ref.next[lvl].load(SeqCst, guard);
+
+                    if succ.tag() == 1 {
+                        let unmarked = succ.with_tag(0);
+                        match pred[lvl].compare_exchange(curr, unmarked, SeqCst, SeqCst, guard) {
+                            Ok(_) => curr = unmarked,
+                            Err(_) => continue 'retry,
+                        }
+                    } else if curr_ref.key < *key {
+                        pred = &curr_ref.next[..];
+                        curr = succ;
+                    } else {
+                        break;
+                    }
+                }
+
+                pos.preds[lvl] = pred;
+                pos.succs[lvl] = curr;
+            }
+
+            return pos;
+        }
+    }
+}
+
+impl<K, V> Default for ConcurrentSkipList<K, V>
+where
+    K: Ord + Send + 'static,
+    V: Send + 'static,
+{
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, 0.5)
+    }
+}
+
+impl<K, V> Drop for ConcurrentSkipList<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut node = self.head[0].load(SeqCst, guard);
+
+            while !node.is_null() {
+                let next = node.deref().next[0].load(SeqCst, guard).with_tag(0);
+                drop(node.into_owned());
+                node = next;
+            }
+        }
+    }
+}
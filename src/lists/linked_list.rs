@@ -66,7 +66,7 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 pub(super) struct NodeIterMut<'a, T: 'a> {
     head: MaybeNone<Node<T>>,
     len: usize,
-    marker: PhantomData<&'a Node<T>>,
+    marker: PhantomData<&'a mut Node<T>>,
 }
 
 impl<'a, T> Iterator for NodeIterMut<'a, T> {
@@ -166,23 +166,22 @@ impl<T> LinkedList<T> {
     }
 
     fn pop_back_node(&mut self) -> Option<Box<Node<T>>> {
+        let tail = self.tail?;
         let new_tail = match self.len {
-            0 | 1 => self.head,
+            1 => None,
             _ => self.get_node(self.len - 2),
         };
 
         unsafe {
-            let node = Some(Box::from_raw(self.tail?.as_ptr()));
+            let node = Box::from_raw(tail.as_ptr());
             self.tail = new_tail;
-
             self.len -= 1;
-            if self.is_empty() {
-                self.head = None;
-                self.tail = None;
-            }
 
-            new_tail?.as_mut().next = None;
-            node
+            match new_tail {
+                None => self.head = None,
+                Some(mut new_tail) => new_tail.as_mut().next = None,
+            }
+            Some(node)
         }
     }
 
@@ -248,7 +247,7 @@ impl<T> LinkedList<T> {
 
         if at == 0 {
             return self.pop_front_node();
-        } else if at == self.len {
+        } else if at == self.len - 1 {
             return self.pop_back_node();
         }
 
@@ -261,7 +260,7 @@ impl<T> LinkedList<T> {
         }
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             head: self.head,
             len: self.len,
@@ -269,7 +268,7 @@ impl<T> LinkedList<T> {
         }
     }
 
-    pub fn iter_mut(&self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             head: self.head,
             len: self.len,
@@ -277,7 +276,7 @@ impl<T> LinkedList<T> {
         }
     }
 
-    pub(super) fn node_iter_mut(&self) -> NodeIterMut<T> {
+    pub(super) fn node_iter_mut(&mut self) -> NodeIterMut<'_, T> {
         NodeIterMut {
             head: self.head,
             len: self.len,
@@ -370,3 +369,76 @@ impl<T> Drop for LinkedList<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::LinkedList;
+    use crate::test_support::{MODEL_SEEDS, MODEL_STEPS};
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use std::collections::VecDeque;
+
+    fn check(list: &LinkedList<String>, model: &VecDeque<String>) {
+        assert_eq!(list.len(), model.len());
+        assert_eq!(list.is_empty(), model.is_empty());
+        assert!(list.iter().eq(model.iter()));
+        assert_eq!(list.iter().size_hint(), (model.len(), Some(model.len())));
+    }
+
+    #[test]
+    fn matches_vec_deque() {
+        for seed in 0..MODEL_SEEDS {
+            let mut rng = Xoshiro256Plus::seed_from_u64(seed);
+            let mut list = LinkedList::new();
+            let mut model = VecDeque::new();
+
+            for step in 0..MODEL_STEPS {
+                let elem = step.to_string();
+                match rng.gen_range(0..10) {
+                    0 => {
+                        list.push_front(elem.clone());
+                        model.push_front(elem);
+                    }
+                    1 => {
+                        list.push_back(elem.clone());
+                        model.push_back(elem);
+                    }
+                    2 => assert_eq!(list.pop_front(), model.pop_front()),
+                    3 => assert_eq!(list.pop_back(), model.pop_back()),
+                    4 => {
+                        let at = rng.gen_range(0..=model.len());
+                        list.insert(elem.clone(), at);
+                        model.insert(at, elem);
+                    }
+                    5 if !model.is_empty() => {
+                        let at = rng.gen_range(0..model.len());
+                        assert_eq!(list.pop(at), model.remove(at));
+                    }
+                    6 => {
+                        list.iter_mut().for_each(|each| each.push('!'));
+                        model.iter_mut().for_each(|each| each.push('!'));
+                    }
+                    7 => {
+                        let clone = list.clone();
+                        check(&clone, &model);
+                        let taken = rng.gen_range(0..=model.len());
+                        assert!(clone
+                            .into_iter()
+                            .take(taken)
+                            .eq(model.iter().take(taken).cloned()));
+                    }
+                    8 if rng.gen_ratio(1, 20) => {
+                        list.clear();
+                        model.clear();
+                    }
+                    _ => {
+                        let elems = rng.gen_range(0..4);
+                        list.extend((0..elems).map(|i| format!("{step}.{i}")));
+                        model.extend((0..elems).map(|i| format!("{step}.{i}")));
+                    }
+                }
+                check(&list, &model);
+            }
+        }
+    }
+}
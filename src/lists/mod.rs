@@ -1,13 +1,29 @@
 use crate::MaybeNone;
+pub use arena_skip_list::{Arena, ArenaSkipList};
+pub use compare::{Compare, Descending, Natural};
+pub use concurrent_skip_list::ConcurrentSkipList;
 use linked_list::LinkedList;
 pub use multi_list::MultiList;
+pub use shared_skip_list::{Shared, SharedSkipList};
 pub use skip_list::SkipList;
+pub use skip_multi_map::SkipMultiMap;
+pub use skip_set::SkipSet;
 use std::cell::RefCell;
 use std::rc::Rc;
+pub use storage::{Boxed, Storage};
 
+pub mod arena_skip_list;
+pub mod compare;
+pub mod concurrent_skip_list;
 mod linked_list;
 pub mod multi_list;
+#[cfg(feature = "serde")]
+mod serde_impls;
+pub mod shared_skip_list;
 pub mod skip_list;
+pub mod skip_multi_map;
+pub mod skip_set;
+pub mod storage;
 
 #[derive(Clone)]
 struct Node<T> {
@@ -29,20 +45,3 @@ impl<T> Node<T> {
         self.elem
     }
 }
-
-#[derive(Clone)]
-struct SkipNode<K, V> {
-    next: Vec<MaybeNone<SkipNode<K, V>>>,
-    key: K,
-    value: V,
-}
-
-impl<K, V> SkipNode<K, V> {
-    fn new(key: K, value: V, level: usize) -> Self {
-        SkipNode {
-            next: vec![None; level + 1],
-            key,
-            value,
-        }
-    }
-}
@@ -1,5 +1,6 @@
 use super::LinkedList;
 use super::Node;
+use crate::error::MultiListError;
 use crate::MaybeNone;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -16,8 +17,17 @@ impl Index {
     pub fn new(level: usize, node: usize) -> Self {
         Index { level, node }
     }
+
+    fn out_of_bounds(&self) -> MultiListError {
+        MultiListError::IndexOutOfBounds {
+            level: self.level,
+            node: self.node,
+        }
+    }
 }
 
+/// Neither `Send` nor `Sync`: every child list is shared through an
+/// `Rc<RefCell<..>>` between its parent node and the level index.
 pub struct MultiList<T> {
     len: usize,
     index_map: HashMap<usize, Vec<Rc<RefCell<LinkedList<T>>>>>,
@@ -47,9 +57,9 @@ impl<T> MultiList<T> {
         Some(lists.iter().map(|ls| ls.borrow().len()).sum())
     }
 
-    pub fn insert(&mut self, at: Index, elem: T) -> Result<(), &str> {
+    pub fn insert(&mut self, at: Index, elem: T) -> Result<(), MultiListError> {
         match self.get_sublist(&at) {
-            None => Err("can't find list at this index"),
+            None => Err(at.out_of_bounds()),
             Some((list, index)) => {
                 (*list).borrow_mut().insert(elem, index);
                 self.len += 1;
@@ -58,17 +68,17 @@ impl<T> MultiList<T> {
         }
     }
 
-    pub fn insert_alt(&mut self, at: Index, elem: T) -> Result<(), &str> {
+    pub fn insert_alt(&mut self, at: Index, elem: T) -> Result<(), MultiListError> {
         if at.node == 0 {
-            return Err("wrong local node index, should be at least 1");
+            return Err(MultiListError::ZeroNodeIndex);
         }
 
         let stub_index = Index::new(at.level, at.node - 1);
         match self.get_sublist(&stub_index) {
-            None => Err("can't find list at this index"),
+            None => Err(at.out_of_bounds()),
             Some((list, index)) => {
                 if index >= list.borrow().len() {
-                    return Err("index out of bounds");
+                    return Err(at.out_of_bounds());
                 }
 
                 (*list).borrow_mut().insert(elem, index + 1);
@@ -78,14 +88,14 @@ impl<T> MultiList<T> {
         }
     }
 
-    pub fn attach_child(&mut self, at: Index, elem: T) -> Result<(), &str> {
+    pub fn attach_child(&mut self, at: Index, elem: T) -> Result<(), MultiListError> {
         match self.get_sublist_node(&at) {
-            None => Err("can't find node at this index"),
+            None => Err(at.out_of_bounds()),
             Some(mut node) => {
                 let node = unsafe { node.as_mut() };
 
                 if node.child.is_some() {
-                    return Err("child already exists");
+                    return Err(MultiListError::ChildExists);
                 } else {
                     let mut list = LinkedList::new();
                     list.push_back(elem);
@@ -99,9 +109,9 @@ impl<T> MultiList<T> {
         }
     }
 
-    pub fn detach_child(&mut self, at: Index) -> Result<(), &str> {
+    pub fn detach_child(&mut self, at: Index) -> Result<(), MultiListError> {
         match self.get_sublist_node(&at) {
-            None => Err("can't find node at this index"),
+            None => Err(at.out_of_bounds()),
             Some(mut node) => {
                 let node = unsafe { node.as_mut() };
                 node.child = None;
@@ -113,12 +123,12 @@ impl<T> MultiList<T> {
         }
     }
 
-    pub fn pop(&mut self, at: Index) -> Result<T, &str> {
+    pub fn pop(&mut self, at: Index) -> Result<T, MultiListError> {
         match self.get_sublist(&at) {
-            None => Err("can't find list at this index"),
+            None => Err(at.out_of_bounds()),
             Some((list, index)) => {
                 if index >= list.borrow().len() {
-                    return Err("index out of bounds");
+                    return Err(at.out_of_bounds());
                 }
 
                 let elem = (*list).borrow_mut().pop(index).unwrap();
@@ -129,48 +139,50 @@ impl<T> MultiList<T> {
         }
     }
 
-    pub fn remove_level(&mut self, level: usize) -> Result<(), &str> {
+    pub fn remove_level(&mut self, level: usize) -> Result<(), MultiListError> {
         if level >= self.levels() {
-            return Err("provided level does not exist");
+            return Err(MultiListError::LevelOutOfBounds { level });
         } else if level == 0 {
             self.clear();
             return Ok(());
         }
 
-        for list in self.index_map.get(&level).unwrap() {
+        for list in self.index_map.get(&(level - 1)).unwrap() {
             for node in list.borrow_mut().node_iter_mut() {
                 node.child = None;
             }
         }
 
-        (level..self.levels()).for_each(|lv| {
-            self.len -= self.level_size(lv).unwrap();
-            self.index_map.remove(&lv);
-        });
-
+        self.update_index(level);
+        self.recalc_size();
         Ok(())
     }
 
-    pub fn move_elem(&mut self, src: Index, dst: Index) -> Result<(), &str> {
-        let maybe_src_list = self.get_sublist(&src);
-        if maybe_src_list.is_none() {
-            return Err("can't find list at source index");
-        }
-
-        let (list, at) = maybe_src_list.unwrap();
-        let node = list.borrow_mut().pop_node(at).unwrap();
-
-        let maybe_dst_list = self.get_sublist(&dst);
-        if maybe_dst_list.is_none() {
-            return Err("can't find list at destination index");
+    /// Moves the element at `src` to `dst` along with its children. `dst` is
+    /// looked up after the element is taken out, so it can't point into
+    /// the element's own children. On error the element stays at `src`.
+    pub fn move_elem(&mut self, src: Index, dst: Index) -> Result<(), MultiListError> {
+        let (src_list, src_at) = match self.get_sublist(&src) {
+            Some((list, at)) if at < list.borrow().len() => (list, at),
+            _ => return Err(src.out_of_bounds()),
+        };
+
+        let node = src_list.borrow_mut().pop_node(src_at).unwrap();
+        self.update_index(src.level + 1);
+
+        match self.get_sublist(&dst) {
+            None => {
+                src_list.borrow_mut().insert_node(node, src_at);
+                self.update_index(src.level + 1);
+                Err(dst.out_of_bounds())
+            }
+            Some((list, at)) => {
+                list.borrow_mut().insert_node(node, at);
+                self.update_index(1);
+                self.recalc_size();
+                Ok(())
+            }
         }
-
-        let (list, at) = maybe_dst_list.unwrap();
-        list.borrow_mut().insert_node(node, at);
-
-        self.update_index(1);
-        self.recalc_size();
-        Ok(())
     }
 
     fn get_sublist_node(&self, at: &Index) -> MaybeNone<Node<T>> {
@@ -207,11 +219,17 @@ impl<T> MultiList<T> {
         }
     }
 
+    /// Rebuilds the index of every level from `from` down. A moved element
+    /// can take its children deeper than the current levels, so this stops
+    /// at the first level left empty rather than at `levels()`.
     fn update_index(&mut self, from: usize) {
-        let from = if from == 0 { 1 } else { from };
-        (from..self.levels()).for_each(|level| {
+        let mut level = from.max(1);
+        while self.index_map.contains_key(&(level - 1)) {
             self.update_level_index(level);
-        });
+            level += 1;
+        }
+
+        self.index_map.retain(|lv, _| *lv < level);
     }
 
     fn recalc_size(&mut self) {
@@ -232,19 +250,16 @@ impl<T> MultiList<T> {
             return vec;
         }
 
-        for list in pointers.unwrap().iter().map(|r| (*r).borrow_mut()) {
+        for mut list in pointers.unwrap().iter().map(|r| (*r).borrow_mut()) {
             for (i, node) in list.node_iter_mut().enumerate() {
-                match &node.child {
-                    Some(child) => {
-                        // This is a hack: we don't need empty lists in our array,
-                        // so we should remove refs to empty children
-                        if child.borrow().is_empty() {
-                            node.child = None;
-                        } else {
-                            vec.push((index_offset + i, child.clone()))
-                        }
+                if let Some(child) = &node.child {
+                    // This is a hack: we don't need empty lists in our array,
+                    // so we should remove refs to empty children
+                    if child.borrow().is_empty() {
+                        node.child = None;
+                    } else {
+                        vec.push((index_offset + i, child.clone()))
                     }
-                    None => {}
                 }
             }
             index_offset += list.len();
@@ -315,3 +330,253 @@ where
         new
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Index, MultiList};
+    use crate::error::MultiListError;
+    use crate::test_support::{MODEL_SEEDS, MODEL_STEPS};
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+
+    /// An element with its child list, empty when it has none.
+    struct Tree {
+        elem: String,
+        children: Vec<Tree>,
+    }
+
+    /// The multilist as a plain tree. A child list is addressed by the
+    /// position of its parent on every level above it.
+    #[derive(Default)]
+    struct Model {
+        roots: Vec<Tree>,
+    }
+
+    impl Model {
+        fn list(&self, path: &[usize]) -> &Vec<Tree> {
+            path.iter().fold(&self.roots, |list, i| &list[*i].children)
+        }
+
+        fn list_mut(&mut self, path: &[usize]) -> &mut Vec<Tree> {
+            path.iter()
+                .fold(&mut self.roots, |list, i| &mut list[*i].children)
+        }
+
+        fn lists(&self, level: usize) -> Vec<Vec<usize>> {
+            let mut paths = vec![vec![]];
+            for _ in 0..level {
+                paths = paths
+                    .iter()
+                    .flat_map(|path| {
+                        let list = self.list(path);
+                        (0..list.len())
+                            .filter(|i| !list[*i].children.is_empty())
+                            .map(|i| [path.as_slice(), &[i]].concat())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+            }
+            paths
+        }
+
+        fn levels(&self) -> usize {
+            (1..).find(|level| self.lists(*level).is_empty()).unwrap()
+        }
+
+        fn level_size(&self, level: usize) -> usize {
+            self.lists(level)
+                .iter()
+                .map(|path| self.list(path).len())
+                .sum()
+        }
+
+        /// Mirrors `MultiList::get_sublist`. `kept` is a list that is still in
+        /// the index even though it may be empty.
+        fn sublist(&self, at: Index, kept: Option<&[usize]>) -> Option<(Vec<usize>, usize)> {
+            let mut lists = self.lists(at.level);
+            if let Some(kept) = kept.filter(|kept| kept.len() == at.level) {
+                if let Err(i) = lists.binary_search_by(|path| path.as_slice().cmp(kept)) {
+                    lists.insert(i, kept.to_vec());
+                }
+            }
+
+            let mut local = at.node;
+            for (i, path) in lists.iter().enumerate() {
+                let len = self.list(path).len();
+                if local < len || i == lists.len() - 1 && local == len {
+                    return Some((path.clone(), local));
+                }
+                local -= len;
+            }
+            None
+        }
+
+        fn node(&self, at: Index) -> Result<(Vec<usize>, usize), MultiListError> {
+            match self.sublist(at, None) {
+                Some((path, i)) if i < self.list(&path).len() => Ok((path, i)),
+                _ => Err(at.out_of_bounds()),
+            }
+        }
+
+        fn insert(&mut self, at: Index, elem: String) -> Result<(), MultiListError> {
+            let (path, i) = self.sublist(at, None).ok_or(at.out_of_bounds())?;
+            self.list_mut(&path).insert(i, Tree::leaf(elem));
+            Ok(())
+        }
+
+        fn insert_alt(&mut self, at: Index, elem: String) -> Result<(), MultiListError> {
+            if at.node == 0 {
+                return Err(MultiListError::ZeroNodeIndex);
+            }
+            let (path, i) = self
+                .node(Index::new(at.level, at.node - 1))
+                .map_err(|_| at.out_of_bounds())?;
+            self.list_mut(&path).insert(i + 1, Tree::leaf(elem));
+            Ok(())
+        }
+
+        fn attach_child(&mut self, at: Index, elem: String) -> Result<(), MultiListError> {
+            let (path, i) = self.node(at)?;
+            let node = &mut self.list_mut(&path)[i];
+            if !node.children.is_empty() {
+                return Err(MultiListError::ChildExists);
+            }
+            node.children.push(Tree::leaf(elem));
+            Ok(())
+        }
+
+        fn detach_child(&mut self, at: Index) -> Result<(), MultiListError> {
+            let (path, i) = self.node(at)?;
+            self.list_mut(&path)[i].children.clear();
+            Ok(())
+        }
+
+        fn pop(&mut self, at: Index) -> Result<String, MultiListError> {
+            let (path, i) = self.node(at)?;
+            Ok(self.list_mut(&path).remove(i).elem)
+        }
+
+        fn remove_level(&mut self, level: usize) -> Result<(), MultiListError> {
+            if level >= self.levels() {
+                return Err(MultiListError::LevelOutOfBounds { level });
+            } else if level == 0 {
+                self.roots.clear();
+            }
+            for path in self.lists(level.saturating_sub(1)) {
+                self.list_mut(&path)
+                    .iter_mut()
+                    .for_each(|node| node.children.clear());
+            }
+            Ok(())
+        }
+
+        fn move_elem(&mut self, src: Index, dst: Index) -> Result<(), MultiListError> {
+            let (src_path, src_i) = self.node(src)?;
+            let node = self.list_mut(&src_path).remove(src_i);
+            match self.sublist(dst, Some(&src_path)) {
+                None => {
+                    self.list_mut(&src_path).insert(src_i, node);
+                    Err(dst.out_of_bounds())
+                }
+                Some((path, i)) => {
+                    self.list_mut(&path).insert(i, node);
+                    Ok(())
+                }
+            }
+        }
+
+        /// What `Display` prints for this tree.
+        fn display(&self) -> String {
+            let mut out = format!("Lv0 - {}\n", Tree::display_list(&self.roots));
+            for level in 0..self.levels() - 1 {
+                let nodes = self
+                    .lists(level)
+                    .into_iter()
+                    .flat_map(|path| self.list(&path).iter());
+                let mut string = String::new();
+                for (i, node) in nodes.enumerate() {
+                    if !node.children.is_empty() {
+                        string.push_str(&format!("{i}:{}  ", Tree::display_list(&node.children)));
+                    }
+                }
+                out.push_str(&format!("Lv{} - {}\n", level + 1, string.trim()));
+            }
+            out
+        }
+    }
+
+    impl Tree {
+        fn leaf(elem: String) -> Self {
+            Tree {
+                elem,
+                children: Vec::new(),
+            }
+        }
+
+        fn display_list(list: &[Tree]) -> String {
+            let elems: String = list.iter().map(|node| format!("{}, ", node.elem)).collect();
+            match list.is_empty() {
+                true => "[]".to_string(),
+                false => format!("[{elems}\x08\x08]"),
+            }
+        }
+    }
+
+    fn check(list: &MultiList<String>, model: &Model) {
+        let levels = model.levels();
+        assert_eq!(list.levels(), levels);
+        assert_eq!(
+            list.size(),
+            (0..levels).map(|lv| model.level_size(lv)).sum::<usize>()
+        );
+        for level in 0..levels {
+            assert_eq!(list.level_size(level), Some(model.level_size(level)));
+        }
+        assert_eq!(list.level_size(levels), None);
+        assert_eq!(list.to_string(), model.display());
+    }
+
+    fn random_index(rng: &mut Xoshiro256Plus, model: &Model) -> Index {
+        let level = rng.gen_range(0..=model.levels());
+        let node = rng.gen_range(0..=model.level_size(level) + 1);
+        Index::new(level, node)
+    }
+
+    #[test]
+    fn matches_tree_model() {
+        for seed in 0..MODEL_SEEDS {
+            let mut rng = Xoshiro256Plus::seed_from_u64(seed);
+            let mut list = MultiList::new();
+            let mut model = Model::default();
+
+            for step in 0..MODEL_STEPS {
+                let elem = step.to_string();
+                let at = random_index(&mut rng, &model);
+                match rng.gen_range(0..16) {
+                    0..=2 => assert_eq!(list.insert(at, elem.clone()), model.insert(at, elem)),
+                    3..=4 => assert_eq!(
+                        list.insert_alt(at, elem.clone()),
+                        model.insert_alt(at, elem)
+                    ),
+                    5..=7 => assert_eq!(
+                        list.attach_child(at, elem.clone()),
+                        model.attach_child(at, elem)
+                    ),
+                    8 => assert_eq!(list.detach_child(at), model.detach_child(at)),
+                    9..=10 => assert_eq!(list.pop(at), model.pop(at)),
+                    11..=13 => {
+                        let dst = random_index(&mut rng, &model);
+                        assert_eq!(list.move_elem(at, dst), model.move_elem(at, dst));
+                    }
+                    14 => check(&list.clone(), &model),
+                    _ if rng.gen_ratio(1, 4) => {
+                        let level = rng.gen_range(0..=model.levels());
+                        assert_eq!(list.remove_level(level), model.remove_level(level));
+                    }
+                    _ => {}
+                }
+                check(&list, &model);
+            }
+        }
+    }
+}
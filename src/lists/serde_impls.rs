@@ -0,0 +1,233 @@
+use crate::lists::compare::Compare;
+use crate::lists::skip_list::{level_for, MAX_LEVEL};
+use crate::lists::{SkipList, SkipSet};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// On-disk layout shared by `SkipList` and `SkipSet`: the level settings
+/// followed by every entry in key order. Tower heights are not stored,
+/// so a deserialized list draws fresh ones.
+///
+/// In capacity mode, `max_level` is at least what `capacity` calls for,
+/// and may be more after an `append`. Anything less can't come from a list
+/// and is rejected.
+#[derive(Serialize, Deserialize)]
+struct Repr<E> {
+    fraction: f64,
+    max_level: usize,
+    /// Only present for lists in capacity mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    capacity: Option<usize>,
+    entries: E,
+}
+
+impl<E> Repr<E> {
+    /// Rejects settings the constructors would panic on, and a `max_level`
+    /// below what `capacity` calls for.
+    fn check<D: Error>(&self) -> Result<(), D> {
+        if !(self.fraction > 0.0 && self.fraction < 1.0) {
+            return Err(D::custom(format_args!(
+                "fraction must be between 0 and 1 exclusive, got {}",
+                self.fraction
+            )));
+        }
+        if self.max_level > MAX_LEVEL {
+            return Err(D::custom(format_args!(
+                "max_level must be at most {MAX_LEVEL}, got {}",
+                self.max_level
+            )));
+        }
+        if let Some(capacity) = self.capacity {
+            let least = level_for(capacity, self.fraction);
+            if self.max_level < least {
+                return Err(D::custom(format_args!(
+                    "max_level must be at least {least} for capacity {capacity}, got {}",
+                    self.max_level
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+const UNORDERED: &str = "keys are not in strictly ascending order";
+
+struct Entries<'a, K, V, C: Compare<K>>(&'a SkipList<K, V, C>);
+
+impl<'a, K, V, C> Serialize for Entries<'a, K, V, C>
+where
+    K: Serialize,
+    V: Serialize,
+    C: Compare<K>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+struct Keys<'a, K: Ord>(&'a SkipSet<K>);
+
+impl<'a, K> Serialize for Keys<'a, K>
+where
+    K: Ord + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter())
+    }
+}
+
+impl<K, V, C> Serialize for SkipList<K, V, C>
+where
+    K: Serialize,
+    V: Serialize,
+    C: Compare<K>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr {
+            fraction: self.fraction(),
+            max_level: self.max_level(),
+            capacity: self.capacity(),
+            entries: Entries(self),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, K, V, C> Deserialize<'de> for SkipList<K, V, C>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    C: Compare<K> + Default,
+{
+    /// Rebuilds the list with a sorted bulk load in O(n).
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = Repr::<Vec<(K, V)>>::deserialize(deserializer)?;
+        repr.check()?;
+        let mut list = match repr.capacity {
+            Some(capacity) => {
+                let mut list =
+                    SkipList::with_capacity_and_comparator(capacity, repr.fraction, C::default());
+                list.raise_max_level(repr.max_level);
+                list
+            }
+            None => SkipList::with_comparator(repr.fraction, repr.max_level, C::default()),
+        };
+
+        // The load checks the order, and merges repeated keys into one entry
+        let count = repr.entries.len();
+        if list.bulk_insert(repr.entries).is_err() || list.len() != count {
+            return Err(D::Error::custom(UNORDERED));
+        }
+        Ok(list)
+    }
+}
+
+impl<K> Serialize for SkipSet<K>
+where
+    K: Ord + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr {
+            fraction: self.fraction(),
+            max_level: self.max_level(),
+            capacity: self.capacity(),
+            entries: Keys(self),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for SkipSet<K>
+where
+    K: Ord + Deserialize<'de>,
+{
+    /// Rebuilds the set with a sorted bulk load in O(n).
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = Repr::<Vec<K>>::deserialize(deserializer)?;
+        repr.check()?;
+        let mut set = match repr.capacity {
+            Some(capacity) => {
+                let mut set = SkipSet::with_capacity(capacity, repr.fraction);
+                set.raise_max_level(repr.max_level);
+                set
+            }
+            None => SkipSet::new(repr.fraction, repr.max_level),
+        };
+
+        let count = repr.entries.len();
+        if set.bulk_insert(repr.entries).is_err() || set.len() != count {
+            return Err(D::Error::custom(UNORDERED));
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lists::{SkipList, SkipSet};
+
+    type List = SkipList<u8, String>;
+
+    #[test]
+    fn capacity_mode_round_trips() {
+        let list: List = (0..40).map(|key| (key, key.to_string())).collect();
+        let json = serde_json::to_string(&list).unwrap();
+        let restored: List = serde_json::from_str(&json).unwrap();
+        assert!(restored.iter().eq(list.iter()));
+        assert_eq!(restored.capacity(), list.capacity());
+
+        let set = SkipSet::from_sorted_iter_with(0.25, 4, 0..40);
+        let json = serde_json::to_string(&set).unwrap();
+        let restored: SkipSet<i32> = serde_json::from_str(&json).unwrap();
+        assert!(restored.iter().eq(set.iter()));
+        assert_eq!((restored.capacity(), restored.max_level()), (None, 4));
+    }
+
+    #[test]
+    fn max_level_above_capacity_is_kept() {
+        let mut list = List::with_capacity(64, 0.5);
+        list.extend((0..10).map(|key| (key, key.to_string())));
+        let mut other = List::new(0.5, 12);
+        other.insert(200, String::new()).unwrap();
+        list.append(&mut other);
+        let json = serde_json::to_string(&list).unwrap();
+        let restored: List = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.capacity(), list.capacity());
+        assert_eq!(restored.max_level(), 12);
+    }
+
+    #[test]
+    fn unordered_keys_are_rejected() {
+        for entries in [r#"[[2,"a"],[1,"b"]]"#, r#"[[1,"a"],[1,"b"]]"#] {
+            let json = format!(r#"{{"fraction":0.5,"max_level":4,"entries":{entries}}}"#);
+            assert!(serde_json::from_str::<List>(&json).is_err(), "{json}");
+        }
+        for entries in ["[2,1]", "[1,1]"] {
+            let json = format!(r#"{{"fraction":0.5,"max_level":4,"entries":{entries}}}"#);
+            assert!(
+                serde_json::from_str::<SkipSet<u8>>(&json).is_err(),
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn bad_settings_are_rejected() {
+        for settings in [
+            r#""fraction":1.0,"max_level":4"#,
+            r#""fraction":-0.5,"max_level":4"#,
+            r#""fraction":0.5,"max_level":18446744073709551615"#,
+            r#""fraction":0.5,"max_level":65536"#,
+            // 1000 entries need 10 levels at this fraction
+            r#""fraction":0.5,"max_level":4,"capacity":1000"#,
+        ] {
+            let json = format!(r#"{{{settings},"entries":[[1,"a"]]}}"#);
+            assert!(serde_json::from_str::<List>(&json).is_err(), "{json}");
+            let json = format!(r#"{{{settings},"entries":[1]}}"#);
+            assert!(
+                serde_json::from_str::<SkipSet<u8>>(&json).is_err(),
+                "{json}"
+            );
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use crate::lists::compare::{Compare, Natural};
+use crate::lists::skip_list::{Iter, SkipList};
+use crate::lists::storage::Nodes;
+use std::fmt::{Debug, Formatter};
+use std::mem::size_of;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// `SkipList` whose nodes can be shared with its copies, so that `clone`
+/// and `snapshot` take O(1). Build one with the `_in` constructors, e.g.
+/// `SkipList::new_in(0.5, 16, Shared::new())`.
+pub type SharedSkipList<K, V, C = Natural> = SkipList<K, V, C, Shared<K, V>>;
+
+/// Storage that keeps every node behind an `Arc`, in a persistent trie
+/// indexed by node handle.
+///
+/// Copies of the storage share the whole trie. A write copies the node it
+/// changes and the trie path leading to it, at most a few dozen pointers
+/// per level, so the nodes nobody writes to are never copied.
+#[derive(Clone)]
+pub struct Shared<K, V> {
+    root: Arc<Trie<K, V>>,
+    /// Branch levels above the leaves.
+    height: usize,
+    /// Slots handed out so far, live or free.
+    len: usize,
+    /// First slot of the chain of free ones.
+    free: usize,
+}
+
+const BITS: usize = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+/// Marks the end of a level and of the free chain.
+const NIL: usize = usize::MAX;
+
+enum Trie<K, V> {
+    Branch(Vec<Arc<Trie<K, V>>>),
+    Leaf(Vec<Slot<K, V>>),
+}
+
+enum Slot<K, V> {
+    Used(Arc<Node<K, V>>),
+    /// Holds the next free slot.
+    Free(usize),
+}
+
+#[derive(Clone, Copy)]
+struct Link {
+    next: usize,
+    /// How many level 0 steps the link skips.
+    span: usize,
+}
+
+const UNLINKED: Link = Link { next: NIL, span: 0 };
+
+#[derive(Clone)]
+struct Node<K, V> {
+    links: Vec<Link>,
+    /// Previous node on level 0, `NIL` for the first one.
+    prev: usize,
+    key: K,
+    value: V,
+}
+
+// Copying a trie level only copies the pointers below it, whatever the
+// entries are, so these do not ask for `K: Clone` as a derive would.
+impl<K, V> Clone for Trie<K, V> {
+    fn clone(&self) -> Self {
+        match self {
+            Trie::Branch(children) => Trie::Branch(children.clone()),
+            Trie::Leaf(slots) => Trie::Leaf(slots.clone()),
+        }
+    }
+}
+
+impl<K, V> Clone for Slot<K, V> {
+    fn clone(&self) -> Self {
+        match self {
+            Slot::Used(node) => Slot::Used(Arc::clone(node)),
+            Slot::Free(next) => Slot::Free(*next),
+        }
+    }
+}
+
+impl<K, V> Trie<K, V> {
+    fn memory(&self) -> usize {
+        size_of::<Self>()
+            + match self {
+                Trie::Branch(children) => {
+                    children.capacity() * size_of::<Arc<Self>>()
+                        + children.iter().map(|child| child.memory()).sum::<usize>()
+                }
+                Trie::Leaf(slots) => slots.capacity() * size_of::<Slot<K, V>>(),
+            }
+    }
+}
+
+impl<K, V> Shared<K, V> {
+    pub fn new() -> Self {
+        Shared {
+            root: Arc::new(Trie::Leaf(Vec::new())),
+            height: 0,
+            len: 0,
+            free: NIL,
+        }
+    }
+
+    fn node(&self, id: usize) -> &Node<K, V> {
+        let mut trie = &*self.root;
+        let mut shift = BITS * self.height;
+        loop {
+            match trie {
+                Trie::Branch(children) => {
+                    trie = &children[(id >> shift) & MASK];
+                    shift -= BITS;
+                }
+                Trie::Leaf(slots) => match &slots[id & MASK] {
+                    Slot::Used(node) => return node,
+                    Slot::Free(_) => panic!("node {id} is free"),
+                },
+            }
+        }
+    }
+}
+
+impl<K: Clone, V: Clone> Shared<K, V> {
+    /// Unshares the path down to the slot, leaving the node itself alone.
+    fn slot_mut(&mut self, id: usize) -> &mut Slot<K, V> {
+        let mut trie = Arc::make_mut(&mut self.root);
+        let mut shift = BITS * self.height;
+        loop {
+            match trie {
+                Trie::Branch(children) => {
+                    trie = Arc::make_mut(&mut children[(id >> shift) & MASK]);
+                    shift -= BITS;
+                }
+                Trie::Leaf(slots) => return &mut slots[id & MASK],
+            }
+        }
+    }
+
+    fn node_mut(&mut self, id: usize) -> &mut Node<K, V> {
+        match self.slot_mut(id) {
+            Slot::Used(node) => Arc::make_mut(node),
+            Slot::Free(_) => panic!("node {id} is free"),
+        }
+    }
+
+    /// Adds a slot after the last one, growing the trie by a level when full.
+    fn push(&mut self, node: Arc<Node<K, V>>) -> usize {
+        let id = self.len;
+        if id == WIDTH << (BITS * self.height) {
+            let root = Arc::new(Trie::Branch(Vec::new()));
+            let old = std::mem::replace(&mut self.root, root);
+            if let Trie::Branch(children) = Arc::make_mut(&mut self.root) {
+                children.push(old);
+            }
+            self.height += 1;
+        }
+
+        let mut trie = Arc::make_mut(&mut self.root);
+        let mut shift = BITS * self.height;
+        loop {
+            match trie {
+                Trie::Branch(children) => {
+                    let index = (id >> shift) & MASK;
+                    if index == children.len() {
+                        children.push(Arc::new(match shift {
+                            BITS => Trie::Leaf(Vec::new()),
+                            _ => Trie::Branch(Vec::new()),
+                        }));
+                    }
+                    trie = Arc::make_mut(&mut children[index]);
+                    shift -= BITS;
+                }
+                Trie::Leaf(slots) => {
+                    slots.push(Slot::Used(node));
+                    break;
+                }
+            }
+        }
+
+        self.len += 1;
+        id
+    }
+}
+
+impl<K, V> Default for Shared<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn handle(next: usize) -> Option<usize> {
+    match next {
+        NIL => None,
+        next => Some(next),
+    }
+}
+
+impl<K: Clone, V: Clone> Nodes<K, V> for Shared<K, V> {
+    type Id = usize;
+
+    const GLOBAL_IDS: bool = false;
+
+    /// Takes the most recently freed slot, or adds one.
+    fn alloc(&mut self, key: K, value: V, level: usize) -> usize {
+        let node = Arc::new(Node {
+            links: vec![UNLINKED; level + 1],
+            prev: NIL,
+            key,
+            value,
+        });
+
+        if self.free == NIL {
+            return self.push(node);
+        }
+
+        let id = self.free;
+        let slot = self.slot_mut(id);
+        let Slot::Free(next) = *slot else {
+            panic!("free chain reached node {id}")
+        };
+        *slot = Slot::Used(node);
+        self.free = next;
+        id
+    }
+
+    /// Copies the entry out when a copy of the storage still holds the node.
+    unsafe fn free(&mut self, node: usize) -> (K, V) {
+        let next = self.free;
+        let Slot::Used(shared) = std::mem::replace(self.slot_mut(node), Slot::Free(next)) else {
+            panic!("node {node} is free")
+        };
+        self.free = node;
+
+        match Arc::try_unwrap(shared) {
+            Ok(node) => (node.key, node.value),
+            Err(shared) => (shared.key.clone(), shared.value.clone()),
+        }
+    }
+
+    unsafe fn adopt(&mut self, from: &mut Self, node: usize) -> usize {
+        let level = from.level(node);
+        let (key, value) = from.free(node);
+        self.alloc(key, value, level)
+    }
+
+    unsafe fn clear(&mut self, _first: Option<usize>) {
+        *self = Self::new();
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        Some(self.clone())
+    }
+
+    unsafe fn level(&self, node: usize) -> usize {
+        self.node(node).links.len() - 1
+    }
+
+    unsafe fn set_level(&mut self, node: usize, level: usize) {
+        self.node_mut(node).links.resize(level + 1, UNLINKED);
+    }
+
+    unsafe fn next(&self, node: usize, lvl: usize) -> Option<usize> {
+        handle(self.node(node).links[lvl].next)
+    }
+
+    unsafe fn set_next(&mut self, node: usize, lvl: usize, to: Option<usize>) {
+        self.node_mut(node).links[lvl].next = to.unwrap_or(NIL);
+    }
+
+    unsafe fn span(&self, node: usize, lvl: usize) -> usize {
+        self.node(node).links[lvl].span
+    }
+
+    unsafe fn span_mut(&mut self, node: usize, lvl: usize) -> &mut usize {
+        &mut self.node_mut(node).links[lvl].span
+    }
+
+    unsafe fn prev(&self, node: usize) -> Option<usize> {
+        handle(self.node(node).prev)
+    }
+
+    unsafe fn set_prev(&mut self, node: usize, to: Option<usize>) {
+        self.node_mut(node).prev = to.unwrap_or(NIL);
+    }
+
+    unsafe fn key(&self, node: usize) -> &K {
+        &self.node(node).key
+    }
+
+    unsafe fn value(&self, node: usize) -> &V {
+        &self.node(node).value
+    }
+
+    unsafe fn entry_mut(&mut self, node: usize) -> (&K, &mut V) {
+        let node = self.node_mut(node);
+        (&node.key, &mut node.value)
+    }
+
+    unsafe fn value_mut(&mut self, node: usize) -> &mut V {
+        &mut self.node_mut(node).value
+    }
+
+    fn memory(&self) -> usize {
+        self.root.memory()
+    }
+
+    unsafe fn node_memory(&self, node: usize) -> usize {
+        let node = self.node(node);
+        size_of::<Node<K, V>>() + node.links.capacity() * size_of::<Link>()
+    }
+}
+
+/// Frozen copy of a list taken by `SkipList::snapshot`.
+///
+/// Dereferences to a `SkipList`, so every read-only method works on it.
+/// Cloning a handle only bumps a reference count.
+pub struct Snapshot<K, V, C = Natural>
+where
+    K: Clone,
+    V: Clone,
+    C: Compare<K>,
+{
+    list: Arc<SharedSkipList<K, V, C>>,
+}
+
+impl<K, V, C> SharedSkipList<K, V, C>
+where
+    K: Clone,
+    V: Clone,
+    C: Compare<K> + Clone,
+{
+    /// Returns a read-only copy of the list as it is now, which stays the same
+    /// while this list keeps changing. Both share their nodes until either
+    /// side writes to one, so taking a snapshot costs O(1), and each later
+    /// write copies only the nodes it touches.
+    pub fn snapshot(&self) -> Snapshot<K, V, C> {
+        Snapshot {
+            list: Arc::new(self.clone()),
+        }
+    }
+}
+
+impl<K, V, C> Snapshot<K, V, C>
+where
+    K: Clone,
+    V: Clone,
+    C: Compare<K>,
+{
+    /// Returns `true` if both handles point to the same copy.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.list, &other.list)
+    }
+}
+
+impl<K, V, C> Deref for Snapshot<K, V, C>
+where
+    K: Clone,
+    V: Clone,
+    C: Compare<K>,
+{
+    type Target = SharedSkipList<K, V, C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.list
+    }
+}
+
+impl<K, V, C> Clone for Snapshot<K, V, C>
+where
+    K: Clone,
+    V: Clone,
+    C: Compare<K>,
+{
+    fn clone(&self) -> Self {
+        Snapshot {
+            list: Arc::clone(&self.list),
+        }
+    }
+}
+
+impl<'a, K, V, C> IntoIterator for &'a Snapshot<K, V, C>
+where
+    K: Clone,
+    V: Clone,
+    C: Compare<K>,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, Shared<K, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+impl<K, V, C> Debug for Snapshot<K, V, C>
+where
+    K: Clone + Debug,
+    V: Clone,
+    C: Compare<K>,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&*self.list, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Shared, SharedSkipList};
+    use std::cell::Cell;
+
+    thread_local! {
+        static CLONES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counts every copy made of it.
+    #[derive(Debug, PartialEq)]
+    struct Counted(u32);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|clones| clones.set(clones.get() + 1));
+            Counted(self.0)
+        }
+    }
+
+    fn clones() -> usize {
+        CLONES.with(|clones| clones.replace(0))
+    }
+
+    #[test]
+    fn snapshot_copies_only_written_nodes() {
+        let mut list = SharedSkipList::with_seed_in(0.5, 16, 7, Shared::new());
+        list.extend((0..1000).map(|key| (key * 2, Counted(key))));
+        clones();
+
+        let snapshot = list.snapshot();
+        assert_eq!(clones(), 0);
+
+        // One predecessor per level changes, and so does the back link
+        // of the successor
+        list.insert(501, Counted(0)).unwrap();
+        assert!(clones() <= list.cur_level() + 2);
+
+        list.find_mut(&40).unwrap().0 += 1;
+        assert_eq!(clones(), 1);
+        list.find_mut(&40).unwrap().0 += 1;
+        assert_eq!(clones(), 0);
+
+        assert_eq!(snapshot.len(), 1000);
+        assert_eq!(snapshot.find(&40), Some(&Counted(20)));
+        assert_eq!(list.find(&40), Some(&Counted(22)));
+        assert!(snapshot.keys().copied().eq((0..1000).map(|key| key * 2)));
+    }
+}
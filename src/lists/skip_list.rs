@@ -1,43 +1,203 @@
-use crate::lists::SkipNode;
-use crate::MaybeNone;
-use rand::{thread_rng, Rng};
+use crate::error::SkipListError;
+use crate::lists::compare::{Compare, Natural};
+use crate::lists::storage::{Boxed, Storage};
+use rand::{thread_rng, Rng, RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256Plus;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
+use std::io;
+use std::iter::Peekable;
 use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::{Bound, RangeBounds};
 use std::ptr::NonNull;
 
-pub struct SkipList<V>
+/// Ordered map built on a skip list.
+///
+/// The head is stored as a bare vector of forward links, so it never needs
+/// a key or a value. Everywhere a node position is passed around as
+/// `Option<S::Id>`, `None` stands for that head. Level 0 is linked
+/// backwards as well and the last node is kept as the tail, so the list can
+/// be walked from either end.
+///
+/// The nodes live in the storage `S` and are reached through the handles it
+/// hands out. `Boxed`, the default, gives every node an allocation of its
+/// own, while `ArenaSkipList` keeps them in a slab.
+///
+/// A list made with `with_capacity` keeps its maximum level at about
+/// `log(n) / log(1 / p)` and raises it whenever the length outgrows `capacity`.
+/// `Default`, `with_fraction` and `FromIterator` start out that way too.
+///
+/// Every constructor panics unless `0 < fraction < 1` and
+/// `max_level <= u16::MAX`.
+pub struct SkipList<K, V, C = Natural, S = Boxed<K, V>>
 where
-    V: Default,
+    C: Compare<K>,
+    S: Storage<K, V>,
 {
-    head: NonNull<SkipNode<usize, V>>,
+    nodes: S,
+    head: Vec<Option<S::Id>>,
+    head_span: Vec<usize>,
+    tail: Option<S::Id>,
+    comparator: C,
     fraction: f64,
     max_level: usize,
+    capacity: Option<usize>,
     cur_level: usize,
     len: usize,
+    rng: Xoshiro256Plus,
+}
+
+/// Rightmost node visited on each level of a descent, paired with its rank.
+type UpdateVec<I> = Vec<(Option<I>, usize)>;
+
+pub(crate) const MAX_LEVEL: usize = u16::MAX as usize;
+
+/// Shape of a list at one point in time, see `SkipList::stats`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    pub len: usize,
+    pub cur_level: usize,
+    pub max_level: usize,
+    /// `levels[i]` counts the nodes whose tower tops out at level `i`.
+    pub levels: Vec<usize>,
+    /// Links in use, the same figure `SkipList::node_ptrs` gives.
+    pub pointers: usize,
+    /// Mean number of steps a lookup of a present key takes. Following
+    /// a link and moving down a level count as one step each.
+    pub avg_search_path: f64,
+    /// Bytes taken by the list and its nodes, without any heap memory
+    /// owned by the keys and values themselves.
+    pub memory: usize,
+}
+
+/// Walks the nodes between two inclusive ends.
+struct RawRange<I> {
+    front: Option<I>,
+    back: Option<I>,
+}
+
+impl<I: Copy + Eq> RawRange<I> {
+    unsafe fn next<K, V, S>(&mut self, nodes: &S) -> Option<I>
+    where
+        S: Storage<K, V, Id = I>,
+    {
+        let node = self.front?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = nodes.next(node, 0);
+        }
+        Some(node)
+    }
+
+    unsafe fn next_back<K, V, S>(&mut self, nodes: &S) -> Option<I>
+    where
+        S: Storage<K, V, Id = I>,
+    {
+        let node = self.back?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = nodes.prev(node);
+        }
+        Some(node)
+    }
+}
+
+pub struct Range<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    nodes: &'a S,
+    inner: RawRange<S::Id>,
+    marker: PhantomData<(&'a K, &'a V)>,
+}
+
+impl<'a, K, V, S> Iterator for Range<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let nodes = self.nodes;
+        unsafe {
+            let node = self.inner.next(nodes)?;
+            Some((nodes.key(node), nodes.value(node)))
+        }
+    }
+}
+
+impl<'a, K, V, S> DoubleEndedIterator for Range<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let nodes = self.nodes;
+        unsafe {
+            let node = self.inner.next_back(nodes)?;
+            Some((nodes.key(node), nodes.value(node)))
+        }
+    }
+}
+
+/// Holds the storage through a raw pointer, as every value it hands out
+/// borrows the storage mutably for `'a`. Storages only ever touch the node
+/// asked for, so no value is reached twice.
+pub struct RangeMut<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    nodes: NonNull<S>,
+    inner: RawRange<S::Id>,
+    marker: PhantomData<(&'a K, &'a mut V, &'a mut S)>,
 }
 
-type UpdateVec<V> = Vec<MaybeNone<SkipNode<usize, V>>>;
+impl<'a, K, V, S> Iterator for RangeMut<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            let node = self.inner.next(self.nodes.as_ref())?;
+            Some((*self.nodes.as_ptr()).entry_mut(node))
+        }
+    }
+}
 
-const MAX_LEVEL: usize = u16::MAX as usize;
+impl<'a, K, V, S> DoubleEndedIterator for RangeMut<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        unsafe {
+            let node = self.inner.next_back(self.nodes.as_ref())?;
+            Some((*self.nodes.as_ptr()).entry_mut(node))
+        }
+    }
+}
 
-struct NodeIter<'a, V: 'a> {
-    current: MaybeNone<SkipNode<usize, V>>,
+struct NodeIter<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a> {
+    nodes: &'a S,
+    front: Option<S::Id>,
+    back: Option<S::Id>,
     len: usize,
-    marker: PhantomData<&'a SkipNode<usize, V>>,
+    marker: PhantomData<(&'a K, &'a V)>,
 }
 
-impl<'a, V> Iterator for NodeIter<'a, V> {
-    type Item = &'a SkipNode<usize, V>;
+impl<'a, K, V, S> Iterator for NodeIter<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = S::Id;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.len == 0 {
             None
         } else {
-            self.current.map(|node| unsafe {
-                let node = node.as_ref();
+            self.front.inspect(|&node| unsafe {
                 self.len -= 1;
-                self.current = node.next[0];
-                node
+                self.front = self.nodes.next(node, 0);
             })
         }
     }
@@ -47,231 +207,2315 @@ impl<'a, V> Iterator for NodeIter<'a, V> {
     }
 }
 
-impl<V> SkipList<V>
+impl<'a, K, V, S> DoubleEndedIterator for NodeIter<'a, K, V, S>
 where
-    V: Default,
+    S: Storage<K, V>,
 {
-    pub fn new(fraction: f64, max_level: usize) -> Self {
-        let nil = SkipNode::<usize, V>::new(usize::MAX, V::default(), max_level);
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            None
+        } else {
+            self.back.inspect(|&node| unsafe {
+                self.len -= 1;
+                self.back = self.nodes.prev(node);
+            })
+        }
+    }
+}
 
-        SkipList {
-            head: NonNull::from(Box::leak(Box::new(nil))),
-            fraction,
-            max_level,
-            cur_level: 0,
-            len: 0,
+pub struct Iter<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    inner: NodeIter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Iterator for Iter<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let nodes = self.inner.nodes;
+        let node = self.inner.next()?;
+        unsafe { Some((nodes.key(node), nodes.value(node))) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, S> DoubleEndedIterator for Iter<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let nodes = self.inner.nodes;
+        let node = self.inner.next_back()?;
+        unsafe { Some((nodes.key(node), nodes.value(node))) }
+    }
+}
+
+/// Holds the storage through a raw pointer, see `RangeMut`.
+pub struct IterMut<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    nodes: NonNull<S>,
+    front: Option<S::Id>,
+    back: Option<S::Id>,
+    len: usize,
+    marker: PhantomData<(&'a K, &'a mut V, &'a mut S)>,
+}
+
+impl<'a, K, V, S> Iterator for IterMut<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            None
+        } else {
+            self.front.map(|node| unsafe {
+                self.len -= 1;
+                self.front = self.nodes.as_ref().next(node, 0);
+                (*self.nodes.as_ptr()).entry_mut(node)
+            })
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, S> DoubleEndedIterator for IterMut<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            None
+        } else {
+            self.back.map(|node| unsafe {
+                self.len -= 1;
+                self.back = self.nodes.as_ref().prev(node);
+                (*self.nodes.as_ptr()).entry_mut(node)
+            })
+        }
+    }
+}
+
+pub struct Keys<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    inner: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Iterator for Keys<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, S> DoubleEndedIterator for Keys<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+pub struct Values<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    inner: Iter<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Iterator for Values<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, S> DoubleEndedIterator for Values<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+pub struct ValuesMut<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    inner: IterMut<'a, K, V, S>,
+}
+
+impl<'a, K, V, S> Iterator for ValuesMut<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V, S> DoubleEndedIterator for ValuesMut<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+pub struct IntoIter<K, V, C: Compare<K> = Natural, S: Storage<K, V> = Boxed<K, V>> {
+    list: SkipList<K, V, C, S>,
+}
+
+impl<K, V, C, S> Iterator for IntoIter<K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<K, V, C, S> DoubleEndedIterator for IntoIter<K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_last()
+    }
+}
+
+/// Entries taken out by `SkipList::drain_range`. The ones left unread are
+/// dropped together with the iterator.
+pub struct DrainRange<'a, K: 'a, V: 'a, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    nodes: &'a mut S,
+    current: Option<S::Id>,
+    len: usize,
+    marker: PhantomData<(K, V)>,
+}
+
+impl<'a, K, V, S> Iterator for DrainRange<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let node = self.current?;
+        unsafe {
+            self.current = self.nodes.next(node, 0);
+            self.len -= 1;
+            Some(self.nodes.free(node))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, S> Drop for DrainRange<'a, K, V, S>
+where
+    S: Storage<K, V>,
+{
+    fn drop(&mut self) {
+        for _ in self.by_ref() {}
+    }
+}
+
+/// Search path shared by `Cursor` and `CursorMut`: the predecessor of the
+/// current entry on every level, paired with its rank. The current entry is
+/// whatever follows the level 0 predecessor, with `None` past the end.
+struct Finger<I> {
+    path: UpdateVec<I>,
+}
+
+impl<I: Copy> Clone for Finger<I> {
+    fn clone(&self) -> Self {
+        Finger {
+            path: self.path.clone(),
+        }
+    }
+}
+
+/// Read-only position in a `SkipList` that remembers how it got there, so
+/// seeking to a nearby key is cheaper than a fresh lookup.
+///
+/// The cursor points at an entry, or past the end once it moves beyond
+/// the last one.
+pub struct Cursor<'a, K: 'a, V: 'a, C = Natural, S = Boxed<K, V>>
+where
+    C: Compare<K> + 'a,
+    S: Storage<K, V> + 'a,
+{
+    list: &'a SkipList<K, V, C, S>,
+    finger: Finger<S::Id>,
+}
+
+impl<'a, K, V, C, S> Cursor<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    pub fn key(&self) -> Option<&'a K> {
+        self.entry().map(|(key, _)| key)
+    }
+
+    pub fn value(&self) -> Option<&'a V> {
+        self.entry().map(|(_, value)| value)
+    }
+
+    pub fn entry(&self) -> Option<(&'a K, &'a V)> {
+        self.list.node_entry(self.list.finger_current(&self.finger))
+    }
+
+    /// Returns the position of the current entry, or the length of the list
+    /// past the end.
+    pub fn index(&self) -> usize {
+        self.finger.path[0].1
+    }
+
+    /// Moves to the first entry with a key not less than `key`, returning
+    /// whether that entry holds `key` itself. Costs O(log d) for a key `d`
+    /// entries away from the current one.
+    pub fn seek(&mut self, key: &K) -> bool {
+        let list = self.list;
+        list.seek_by(&mut self.finger, |k| list.compare(k, key).is_lt());
+        list.holds_key(list.finger_current(&self.finger), key)
+    }
+
+    /// Moves to the next entry, or past the end from the last one.
+    pub fn move_next(&mut self) {
+        self.list.finger_next(&mut self.finger)
+    }
+
+    /// Moves to the previous entry. Does nothing at the first one.
+    pub fn move_prev(&mut self) {
+        self.list.finger_prev(&mut self.finger)
+    }
+}
+
+impl<'a, K, V, C, S> Clone for Cursor<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    fn clone(&self) -> Self {
+        Cursor {
+            list: self.list,
+            finger: self.finger.clone(),
+        }
+    }
+}
+
+/// Same as `Cursor`, but also able to change the list around its position.
+/// Inserting and removing reuse the search path instead of descending
+/// from the head again.
+pub struct CursorMut<'a, K: 'a, V: 'a, C = Natural, S = Boxed<K, V>>
+where
+    C: Compare<K> + 'a,
+    S: Storage<K, V> + 'a,
+{
+    list: &'a mut SkipList<K, V, C, S>,
+    finger: Finger<S::Id>,
+}
+
+impl<'a, K, V, C, S> CursorMut<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    pub fn key(&self) -> Option<&K> {
+        self.entry().map(|(key, _)| key)
+    }
+
+    pub fn value(&self) -> Option<&V> {
+        self.entry().map(|(_, value)| value)
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        let node = self.list.finger_current(&self.finger)?;
+        unsafe { Some(self.list.nodes.value_mut(node)) }
+    }
+
+    pub fn entry(&self) -> Option<(&K, &V)> {
+        self.list.node_entry(self.list.finger_current(&self.finger))
+    }
+
+    /// See `Cursor::index`.
+    pub fn index(&self) -> usize {
+        self.finger.path[0].1
+    }
+
+    /// See `Cursor::seek`.
+    pub fn seek(&mut self, key: &K) -> bool {
+        let list = &*self.list;
+        list.seek_by(&mut self.finger, |k| list.compare(k, key).is_lt());
+        list.holds_key(list.finger_current(&self.finger), key)
+    }
+
+    pub fn move_next(&mut self) {
+        self.list.finger_next(&mut self.finger)
+    }
+
+    pub fn move_prev(&mut self) {
+        self.list.finger_prev(&mut self.finger)
+    }
+
+    /// Inserts the pair right after the current entry without moving the
+    /// cursor. Past the end, the pair goes in front of the first entry.
+    ///
+    /// Fails if `key` does not fall strictly between the keys around the
+    /// insertion point.
+    pub fn insert_after(&mut self, key: K, value: V) -> Result<(), SkipListError> {
+        let list = &mut *self.list;
+        let current = list.finger_current(&self.finger);
+        unsafe {
+            if let Some(node) = current {
+                let current_key = list.nodes.key(node);
+                if list.compare(&key, current_key).is_le() {
+                    return Err(list.misplaced(current_key, &key));
+                }
+            }
+            if let Some(next) = list.next(current, 0) {
+                let next_key = list.nodes.key(next);
+                if list.compare(&key, next_key).is_ge() {
+                    return Err(list.misplaced(next_key, &key));
+                }
+            }
+        }
+
+        let path = &mut self.finger.path;
+        unsafe {
+            match current {
+                Some(node) => {
+                    // The new node follows the current one, so it only
+                    // becomes the predecessor on levels the path lacks
+                    let height = list.nodes.level(node) + 1;
+                    let rank = path[0].1 + 1;
+                    let mut update = path.clone();
+                    update
+                        .iter_mut()
+                        .take(height)
+                        .for_each(|each| *each = (Some(node), rank));
+                    list.insert_node(&update, key, value);
+                    path.resize(list.cur_level + 1, (None, 0));
+                }
+                None => {
+                    // Past the end, every node of the path shifts right by one
+                    let node = list.insert_node(&vec![(None, 0)], key, value);
+                    let height = list.nodes.level(node) + 1;
+                    path.resize(list.cur_level + 1, (None, 0));
+                    for (lvl, each) in path.iter_mut().enumerate() {
+                        if each.0.is_some() {
+                            each.1 += 1;
+                        } else if lvl < height {
+                            *each = (Some(node), 1);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the current entry and moves on to the one after it.
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let node = self.list.finger_current(&self.finger)?;
+        unsafe {
+            self.list.unlink_node(node, &self.finger.path);
+            self.finger.path.truncate(self.list.cur_level + 1);
+            Some(self.list.nodes.free(node))
+        }
+    }
+}
+
+/// Walks two lists side by side, pairing up the entries with equal keys.
+struct MergeIter<'a, K: 'a, V: 'a, C: 'a, S: Storage<K, V> + 'a> {
+    left: Peekable<Iter<'a, K, V, S>>,
+    right: Peekable<Iter<'a, K, V, S>>,
+    comparator: &'a C,
+}
+
+impl<'a, K, V, C, S> Iterator for MergeIter<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (Option<(&'a K, &'a V)>, Option<(&'a K, &'a V)>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.left.peek(), self.right.peek()) {
+            (None, None) => return None,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(left), Some(right)) => self.comparator.compare(left.0, right.0),
+        };
+
+        Some(match order {
+            Ordering::Less => (self.left.next(), None),
+            Ordering::Greater => (None, self.right.next()),
+            Ordering::Equal => (self.left.next(), self.right.next()),
+        })
+    }
+}
+
+/// Entries of either list in key order. Keys present in both lists
+/// come with the value of the left one.
+pub struct Union<'a, K: 'a, V: 'a, C: 'a = Natural, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    inner: MergeIter<'a, K, V, C, S>,
+}
+
+impl<'a, K, V, C, S> Iterator for Union<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (left, right) = self.inner.next()?;
+        left.or(right)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.inner.left.size_hint().0;
+        let right = self.inner.right.size_hint().0;
+        (left.max(right), Some(left + right))
+    }
+}
+
+/// Entries of the left list whose keys are also in the right one.
+pub struct Intersection<'a, K: 'a, V: 'a, C: 'a = Natural, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    inner: MergeIter<'a, K, V, C, S>,
+}
+
+impl<'a, K, V, C, S> Iterator for Intersection<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(left), Some(_)) = self.inner.next()? {
+                return Some(left);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.inner.left.size_hint().0;
+        let right = self.inner.right.size_hint().0;
+        (0, Some(left.min(right)))
+    }
+}
+
+/// Entries of the left list whose keys are not in the right one.
+pub struct Difference<'a, K: 'a, V: 'a, C: 'a = Natural, S: Storage<K, V> + 'a = Boxed<K, V>> {
+    inner: MergeIter<'a, K, V, C, S>,
+}
+
+impl<'a, K, V, C, S> Iterator for Difference<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let (Some(left), None) = self.inner.next()? {
+                return Some(left);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.inner.left.size_hint().0;
+        let right = self.inner.right.size_hint().0;
+        (left.saturating_sub(right), Some(left))
+    }
+}
+
+/// Entries whose keys are in exactly one of the lists, in key order.
+pub struct SymmetricDifference<
+    'a,
+    K: 'a,
+    V: 'a,
+    C: 'a = Natural,
+    S: Storage<K, V> + 'a = Boxed<K, V>,
+> {
+    inner: MergeIter<'a, K, V, C, S>,
+}
+
+impl<'a, K, V, C, S> Iterator for SymmetricDifference<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                (Some(entry), None) | (None, Some(entry)) => return Some(entry),
+                _ => continue,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.inner.left.size_hint().0;
+        let right = self.inner.right.size_hint().0;
+        (0, Some(left + right))
+    }
+}
+
+pub enum Entry<'a, K: 'a, V: 'a, C = Natural, S = Boxed<K, V>>
+where
+    C: Compare<K> + 'a,
+    S: Storage<K, V> + 'a,
+{
+    Occupied(OccupiedEntry<'a, K, V, C, S>),
+    Vacant(VacantEntry<'a, K, V, C, S>),
+}
+
+pub struct OccupiedEntry<'a, K: 'a, V: 'a, C = Natural, S = Boxed<K, V>>
+where
+    C: Compare<K> + 'a,
+    S: Storage<K, V> + 'a,
+{
+    list: &'a mut SkipList<K, V, C, S>,
+    node: S::Id,
+}
+
+pub struct VacantEntry<'a, K: 'a, V: 'a, C = Natural, S = Boxed<K, V>>
+where
+    C: Compare<K> + 'a,
+    S: Storage<K, V> + 'a,
+{
+    list: &'a mut SkipList<K, V, C, S>,
+    update: UpdateVec<S::Id>,
+    key: K,
+}
+
+impl<'a, K, V, C, S> Entry<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+        }
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut())
+        }
+        self
+    }
+}
+
+impl<'a, K, V, C, S> Entry<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+    V: Default,
+{
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V, C, S> OccupiedEntry<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    pub fn key(&self) -> &K {
+        unsafe { self.list.nodes.key(self.node) }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { self.list.nodes.value(self.node) }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { self.list.nodes.value_mut(self.node) }
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { self.list.nodes.value_mut(self.node) }
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        unsafe {
+            let (_, update) = self.list.find_node_update(self.list.nodes.key(self.node));
+            self.list.unlink_node(self.node, &update);
+            self.list.nodes.free(self.node).1
+        }
+    }
+}
+
+impl<'a, K, V, C, S> VacantEntry<'a, K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        unsafe {
+            let node = self.list.insert_node(&self.update, self.key, value);
+            self.list.nodes.value_mut(node)
+        }
+    }
+}
+
+impl<K, V> SkipList<K, V>
+where
+    K: Ord,
+{
+    pub fn new(fraction: f64, max_level: usize) -> Self {
+        Self::new_in(fraction, max_level, Boxed::new())
+    }
+
+    /// Creates an empty list in capacity mode.
+    pub fn with_fraction(fraction: f64) -> Self {
+        Self::with_capacity(0, fraction)
+    }
+
+    /// Creates a list with just enough levels for `capacity` entries.
+    /// The maximum level keeps growing once the list gets longer than that.
+    pub fn with_capacity(capacity: usize, fraction: f64) -> Self {
+        Self::with_capacity_in(capacity, fraction, Boxed::new())
+    }
+
+    /// Creates a list whose tower heights are fully determined by `seed`.
+    pub fn with_seed(fraction: f64, max_level: usize, seed: u64) -> Self {
+        Self::with_seed_in(fraction, max_level, seed, Boxed::new())
+    }
+
+    /// Creates a list that draws its level generator's seed from `rng`.
+    pub fn with_rng<R: RngCore>(fraction: f64, max_level: usize, rng: &mut R) -> Self {
+        Self::with_seed(fraction, max_level, rng.next_u64())
+    }
+}
+
+impl<K, V, C> SkipList<K, V, C>
+where
+    C: Compare<K>,
+{
+    /// Creates a list that orders its keys by `comparator`.
+    pub fn with_comparator(fraction: f64, max_level: usize, comparator: C) -> Self {
+        Self::with_comparator_in(fraction, max_level, comparator, Boxed::new())
+    }
+
+    pub fn with_capacity_and_comparator(capacity: usize, fraction: f64, comparator: C) -> Self {
+        Self::with_capacity_and_comparator_in(capacity, fraction, comparator, Boxed::new())
+    }
+
+    pub fn with_seed_and_comparator(
+        fraction: f64,
+        max_level: usize,
+        seed: u64,
+        comparator: C,
+    ) -> Self {
+        Self::with_seed_and_comparator_in(fraction, max_level, seed, comparator, Boxed::new())
+    }
+}
+
+/// Constructors for a list on any storage, which they take as their last
+/// argument, the way `Vec::new_in` takes an allocator. The ones without the
+/// `_in` suffix are for the default `Boxed` storage only, so that a bare
+/// `SkipList::new` never leaves the storage for type inference to guess.
+impl<K, V, S> SkipList<K, V, Natural, S>
+where
+    K: Ord,
+    S: Storage<K, V>,
+{
+    /// Same as `new`, keeping the nodes in `storage`.
+    pub fn new_in(fraction: f64, max_level: usize, storage: S) -> Self {
+        Self::with_comparator_in(fraction, max_level, Natural, storage)
+    }
+
+    /// Same as `with_capacity`, keeping the nodes in `storage`.
+    pub fn with_capacity_in(capacity: usize, fraction: f64, storage: S) -> Self {
+        Self::with_capacity_and_comparator_in(capacity, fraction, Natural, storage)
+    }
+
+    /// Same as `with_seed`, keeping the nodes in `storage`.
+    pub fn with_seed_in(fraction: f64, max_level: usize, seed: u64, storage: S) -> Self {
+        Self::with_seed_and_comparator_in(fraction, max_level, seed, Natural, storage)
+    }
+}
+
+impl<K, V, C, S> SkipList<K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    /// Same as `with_comparator`, keeping the nodes in `storage`.
+    pub fn with_comparator_in(fraction: f64, max_level: usize, comparator: C, storage: S) -> Self {
+        Self::with_seed_and_comparator_in(
+            fraction,
+            max_level,
+            thread_rng().gen(),
+            comparator,
+            storage,
+        )
+    }
+
+    /// Same as `with_capacity_and_comparator`, keeping the nodes in `storage`.
+    pub fn with_capacity_and_comparator_in(
+        capacity: usize,
+        fraction: f64,
+        comparator: C,
+        storage: S,
+    ) -> Self {
+        Self::from_capacity(capacity, fraction, thread_rng().gen(), comparator, storage)
+    }
+
+    /// Same as `with_seed_and_comparator`, keeping the nodes in `storage`.
+    pub fn with_seed_and_comparator_in(
+        fraction: f64,
+        max_level: usize,
+        seed: u64,
+        comparator: C,
+        storage: S,
+    ) -> Self {
+        assert!(
+            fraction > 0.0 && fraction < 1.0,
+            "fraction must be between 0 and 1 exclusive, got {fraction}"
+        );
+        assert!(
+            max_level <= MAX_LEVEL,
+            "max_level must be at most {MAX_LEVEL}, got {max_level}"
+        );
+
+        SkipList {
+            nodes: storage,
+            head: vec![None; max_level + 1],
+            head_span: vec![0; max_level + 1],
+            tail: None,
+            comparator,
+            fraction,
+            max_level,
+            capacity: None,
+            cur_level: 0,
+            len: 0,
+            rng: Xoshiro256Plus::seed_from_u64(seed),
+        }
+    }
+
+    /// Same as `with_seed_and_comparator_in`, for a list in capacity mode.
+    fn from_capacity(capacity: usize, fraction: f64, seed: u64, comparator: C, storage: S) -> Self {
+        let mut list = Self::with_seed_and_comparator_in(fraction, 0, seed, comparator, storage);
+        list.grow(capacity.max(1));
+        list
+    }
+
+    /// Restarts level generation from `seed` for every following insertion.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = Xoshiro256Plus::seed_from_u64(seed)
+    }
+
+    pub fn clear(&mut self) {
+        let first = self.detach();
+        unsafe { self.nodes.clear(first) }
+    }
+
+    pub fn comparator(&self) -> &C {
+        &self.comparator
+    }
+
+    fn compare(&self, a: &K, b: &K) -> Ordering {
+        self.comparator.compare(a, b)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn cur_level(&self) -> usize {
+        self.cur_level
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    pub fn max_level(&self) -> usize {
+        self.max_level
+    }
+
+    /// Returns the length at which the maximum level is raised next,
+    /// or `None` if the list was made with a fixed maximum level.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Result<(), SkipListError> {
+        let (current, update) = self.find_node_update(&key);
+        if self.holds_key(current, &key) {
+            Err(SkipListError::DuplicateKey)
+        } else {
+            unsafe { self.insert_node(&update, key, value) };
+            Ok(())
+        }
+    }
+
+    /// Inserts the pair, returning the value that was stored under `key` before.
+    pub fn insert_or_replace(&mut self, key: K, value: V) -> Option<V> {
+        let (current, update) = self.find_node_update(&key);
+        if self.holds_key(current, &key) {
+            let old = unsafe { self.nodes.value_mut(current?) };
+            Some(std::mem::replace(old, value))
+        } else {
+            unsafe { self.insert_node(&update, key, value) };
+            None
+        }
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, C, S> {
+        let (current, update) = self.find_node_update(&key);
+        match current {
+            Some(node) if self.holds_key(current, &key) => {
+                Entry::Occupied(OccupiedEntry { list: self, node })
+            }
+            _ => Entry::Vacant(VacantEntry {
+                list: self,
+                update,
+                key,
+            }),
+        }
+    }
+
+    fn holds_key(&self, node: Option<S::Id>, key: &K) -> bool {
+        match node {
+            None => false,
+            Some(node) => unsafe { self.compare(self.nodes.key(node), key).is_eq() },
+        }
+    }
+
+    /// Picks the error for a `key` that can't be placed next to `neighbour`.
+    fn misplaced(&self, neighbour: &K, key: &K) -> SkipListError {
+        if self.compare(neighbour, key).is_eq() {
+            SkipListError::DuplicateKey
+        } else {
+            SkipListError::UnorderedKey
+        }
+    }
+
+    /// Links a new node right after the nodes collected in `update`.
+    unsafe fn insert_node(&mut self, update: &UpdateVec<S::Id>, key: K, value: V) -> S::Id {
+        let level = self.random_level();
+
+        // Levels above `cur_level` are already pointing at the head,
+        // which spans the whole list there
+        if level > self.cur_level {
+            for lvl in self.cur_level + 1..=level {
+                self.head_span[lvl] = self.len;
+            }
+            self.cur_level = level
+        }
+
+        let node = self.nodes.alloc(key, value, level);
+        let rank = update[0].1 + 1;
+
+        for i in 0..=self.cur_level {
+            // Levels the update vector does not reach start at the head
+            let (each, each_rank) = update.get(i).copied().unwrap_or((None, 0));
+            if i > level {
+                *self.span_mut(each, i) += 1;
+                continue;
+            }
+
+            let distance = rank - each_rank;
+            self.set_next(Some(node), i, self.next(each, i));
+            *self.nodes.span_mut(node, i) = self.span(each, i) + 1 - distance;
+            self.set_next(each, i, Some(node));
+            *self.span_mut(each, i) = distance;
+        }
+
+        self.len += 1;
+        node
+    }
+
+    /// Draws the height of the node about to be added, first raising
+    /// the maximum level if that node would overflow the capacity.
+    fn random_level(&mut self) -> usize {
+        if let Some(capacity) = self.capacity {
+            if self.len >= capacity {
+                self.grow(self.len * 2);
+            }
+        }
+
+        let mut level = 0;
+
+        while self.rng.gen_range(0.0..1.0) < self.fraction && level < self.max_level {
+            level += 1
+        }
+
+        level
+    }
+
+    fn grow(&mut self, capacity: usize) {
+        self.capacity = Some(capacity);
+        self.raise_max_level(level_for(capacity, self.fraction));
+    }
+
+    pub(crate) fn raise_max_level(&mut self, max_level: usize) {
+        let max_level = max_level.min(MAX_LEVEL);
+        if max_level > self.max_level {
+            self.max_level = max_level;
+            self.head.resize(max_level + 1, None);
+            self.head_span.resize(max_level + 1, 0);
+        }
+    }
+
+    /// Drops the empty levels from the top of the list.
+    fn shrink_cur_level(&mut self) {
+        while self.cur_level > 0 && self.head[self.cur_level].is_none() {
+            self.cur_level -= 1;
+        }
+    }
+
+    /// Rebuilds the towers into a perfect skip list: with `step = 1 / fraction`,
+    /// the node at position `i` (counting from 1) gets one level for every
+    /// time `step` divides `i`. The result does not depend on any randomness.
+    pub fn rebalance(&mut self) {
+        let step = ((1.0 / self.fraction).round() as usize).max(2);
+        let nodes: Vec<_> = self.node_iter().collect();
+
+        self.head.fill(None);
+        self.tail = None;
+        self.cur_level = 0;
+        self.len = 0;
+
+        let mut tails = Vec::new();
+        for (i, node) in nodes.into_iter().enumerate() {
+            let mut pos = i + 1;
+            let mut level = 0;
+            while pos % step == 0 && level < self.max_level {
+                pos /= step;
+                level += 1;
+            }
+
+            unsafe {
+                self.nodes.set_level(node, level);
+                self.push_back_node(&mut tails, node);
+            }
+        }
+
+        unsafe { self.close_tails(&tails) }
+    }
+
+    /// Builds a list from entries sorted by key in O(n), linking every node
+    /// at the tail instead of searching for its place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not in ascending order.
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        C: Default,
+    {
+        Self::from_sorted_list(Self::default(), iter)
+    }
+
+    /// Same as `from_sorted_iter`, for a list with the given `fraction`
+    /// and `max_level`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not in ascending order.
+    pub fn from_sorted_iter_with<I>(fraction: f64, max_level: usize, iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        C: Default,
+    {
+        Self::from_sorted_list(
+            Self::with_comparator_in(fraction, max_level, C::default(), S::default()),
+            iter,
+        )
+    }
+
+    fn from_sorted_list<I>(mut list: Self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        if list.bulk_insert(iter).is_err() {
+            panic!("keys of a sorted batch must be ascending");
+        }
+        list
+    }
+
+    /// Merges a batch of entries sorted by key in a single forward pass.
+    /// Keys that are already present get their value replaced, and so does
+    /// a key repeated in the batch.
+    ///
+    /// Returns `UnorderedKey` and leaves the list unchanged if the keys
+    /// are not in ascending order. An empty list takes the batch in as it
+    /// streams by, dropping the nodes linked so far on such an error.
+    /// Otherwise the whole batch is collected and checked before the merge
+    /// starts, as replaced values could not be put back, so it takes memory
+    /// for the batch on top of the list.
+    pub fn bulk_insert<I>(&mut self, iter: I) -> Result<(), SkipListError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        if self.is_empty() {
+            return self.append_sorted(iter.into_iter());
+        }
+
+        let batch: Vec<_> = iter.into_iter().collect();
+        if batch
+            .windows(2)
+            .any(|pair| self.compare(&pair[0].0, &pair[1].0).is_gt())
+        {
+            return Err(SkipListError::UnorderedKey);
+        }
+
+        self.merge_sorted(batch);
+        Ok(())
+    }
+
+    fn merge_sorted(&mut self, batch: Vec<(K, V)>) {
+        let mut update: UpdateVec<S::Id> = vec![(None, 0); self.cur_level + 1];
+        for (key, value) in batch {
+            unsafe {
+                if let Some(prev) = update[0].0 {
+                    if self.compare(&key, self.nodes.key(prev)).is_eq() {
+                        *self.nodes.value_mut(prev) = value;
+                        continue;
+                    }
+                }
+
+                // Every level resumes from where the previous key left it,
+                // unless the level above already got further
+                let mut current = (None, 0);
+                for lvl in (0..=self.cur_level).rev() {
+                    if update[lvl].1 > current.1 {
+                        current = update[lvl];
+                    }
+
+                    while let Some(next) = self.next(current.0, lvl) {
+                        if self.compare(self.nodes.key(next), &key).is_lt() {
+                            current = (Some(next), current.1 + self.span(current.0, lvl));
+                        } else {
+                            break;
+                        }
+                    }
+                    update[lvl] = current;
+                }
+
+                let rank = update[0].1 + 1;
+                let next = self.next(update[0].0, 0);
+                let node = match next {
+                    Some(next) if self.holds_key(Some(next), &key) => {
+                        *self.nodes.value_mut(next) = value;
+                        next
+                    }
+                    _ => {
+                        let node = self.insert_node(&update, key, value);
+                        update.resize(self.cur_level + 1, (None, 0));
+                        node
+                    }
+                };
+
+                let height = self.nodes.level(node) + 1;
+                for each in update.iter_mut().take(height) {
+                    *each = (Some(node), rank);
+                }
+            }
+        }
+    }
+
+    /// Links an ascending batch into an empty list, checking the order as
+    /// it goes. A key out of order empties the list again and restores the
+    /// levels it had.
+    fn append_sorted<I>(&mut self, batch: I) -> Result<(), SkipListError>
+    where
+        I: Iterator<Item = (K, V)>,
+    {
+        let (capacity, max_level) = (self.capacity, self.max_level);
+        let mut tails: UpdateVec<S::Id> = vec![(None, 0)];
+
+        for (key, value) in batch {
+            unsafe {
+                if let Some(tail) = tails[0].0 {
+                    match self.compare(&key, self.nodes.key(tail)) {
+                        Ordering::Greater => {}
+                        Ordering::Equal => {
+                            *self.nodes.value_mut(tail) = value;
+                            continue;
+                        }
+                        Ordering::Less => {
+                            self.close_tails(&tails);
+                            self.clear();
+                            self.capacity = capacity;
+                            self.max_level = max_level;
+                            self.head.truncate(max_level + 1);
+                            self.head_span.truncate(max_level + 1);
+                            return Err(SkipListError::UnorderedKey);
+                        }
+                    }
+                }
+
+                let level = self.random_level();
+                let node = self.nodes.alloc(key, value, level);
+                self.push_back_node(&mut tails, node);
+            }
+        }
+
+        unsafe { self.close_tails(&tails) };
+        Ok(())
+    }
+
+    /// Links `node` after all other nodes, keeping in `tails` the last node
+    /// of every level with its rank. The spans leading to the end of the list
+    /// are only set once `close_tails` is called. Levels missing from `tails`
+    /// are taken to end at the head.
+    unsafe fn push_back_node(&mut self, tails: &mut UpdateVec<S::Id>, node: S::Id) {
+        let level = self.nodes.level(node);
+        self.cur_level = self.cur_level.max(level);
+        self.len += 1;
+
+        if tails.len() <= level {
+            tails.resize(level + 1, (None, 0));
+        }
+
+        for (i, (tail, rank)) in tails.iter_mut().enumerate().take(level + 1) {
+            self.set_next(Some(node), i, None);
+            self.set_next(*tail, i, Some(node));
+            *self.span_mut(*tail, i) = self.len - *rank;
+            *tail = Some(node);
+            *rank = self.len;
+        }
+    }
+
+    unsafe fn close_tails(&mut self, tails: &UpdateVec<S::Id>) {
+        for (i, &(tail, rank)) in tails.iter().enumerate().take(self.cur_level + 1) {
+            *self.span_mut(tail, i) = self.len - rank;
+        }
+    }
+
+    pub fn pop(&mut self, key: &K) -> Option<V> {
+        let (current, update) = self.find_node_update(key);
+        if !self.holds_key(current, key) {
+            return None;
+        }
+
+        unsafe {
+            self.unlink_node(current?, &update);
+            Some(self.nodes.free(current?).1)
+        }
+    }
+
+    /// Detaches `node` from the nodes collected in `update` without freeing it.
+    unsafe fn unlink_node(&mut self, node: S::Id, update: &UpdateVec<S::Id>) {
+        for (i, &(each, _)) in update.iter().enumerate().take(self.cur_level + 1) {
+            if self.next(each, i) == Some(node) {
+                *self.span_mut(each, i) += self.nodes.span(node, i);
+                self.set_next(each, i, self.nodes.next(node, i))
+            }
+            *self.span_mut(each, i) -= 1;
+        }
+
+        self.shrink_cur_level();
+        self.len -= 1;
+    }
+
+    /// Keeps only the entries for which `keep` returns `true`, relinking the
+    /// survivors in a single pass without changing their tower heights.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        let mut node = self.detach();
+        let mut tails = Vec::new();
+
+        while let Some(each) = node {
+            unsafe {
+                node = self.nodes.next(each, 0);
+                let (key, value) = self.nodes.entry_mut(each);
+                if keep(key, value) {
+                    self.push_back_node(&mut tails, each);
+                } else {
+                    self.nodes.free(each);
+                }
+            }
+        }
+
+        unsafe { self.close_tails(&tails) }
+    }
+
+    /// Takes the entries with keys in `range` out of the list. The whole run
+    /// is unlinked at once, in O(log n) plus one step per removed tower level.
+    pub fn drain_range<R>(&mut self, range: R) -> DrainRange<'_, K, V, S>
+    where
+        R: RangeBounds<K>,
+    {
+        let (current, len) = self.unlink_range(range);
+        DrainRange {
+            nodes: &mut self.nodes,
+            current,
+            len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Drops the entries with keys in `range`, returning how many there were.
+    pub fn remove_range<R>(&mut self, range: R) -> usize
+    where
+        R: RangeBounds<K>,
+    {
+        self.drain_range(range).len
+    }
+
+    /// Unlinks the nodes with keys in `range`, returning the first of them
+    /// and their count. They stay chained on level 0.
+    fn unlink_range<R>(&mut self, range: R) -> (Option<S::Id>, usize)
+    where
+        R: RangeBounds<K>,
+    {
+        let (first, before) = match range.start_bound() {
+            Bound::Included(start) => {
+                self.find_node_update_by(|key| self.compare(key, start).is_lt())
+            }
+            Bound::Excluded(start) => {
+                self.find_node_update_by(|key| self.compare(key, start).is_le())
+            }
+            Bound::Unbounded => self.find_node_update_by(|_| false),
+        };
+        let (_, last) = match range.end_bound() {
+            Bound::Included(end) => self.find_node_update_by(|key| self.compare(key, end).is_le()),
+            Bound::Excluded(end) => self.find_node_update_by(|key| self.compare(key, end).is_lt()),
+            Bound::Unbounded => self.find_node_update_by(|_| true),
+        };
+
+        let count = last[0].1.saturating_sub(before[0].1);
+        if count == 0 {
+            return (None, 0);
+        }
+
+        unsafe {
+            for (i, (&(each, rank), &(tail, tail_rank))) in before.iter().zip(&last).enumerate() {
+                if tail_rank > rank {
+                    // The level has nodes in the run, skip over all of them
+                    let distance = tail_rank + self.span(tail, i) - rank;
+                    self.set_next(each, i, self.next(tail, i));
+                    *self.span_mut(each, i) = distance - count;
+                } else {
+                    *self.span_mut(each, i) -= count;
+                }
+            }
+        }
+
+        self.shrink_cur_level();
+        self.len -= count;
+        (first, count)
+    }
+
+    fn find_node_update(&self, key: &K) -> (Option<S::Id>, UpdateVec<S::Id>) {
+        self.find_node_update_by(|each| self.compare(each, key).is_lt())
+    }
+
+    /// Collects the last node on every level for which `precedes` holds.
+    fn find_node_update_by<F>(&self, precedes: F) -> (Option<S::Id>, UpdateVec<S::Id>)
+    where
+        F: Fn(&K) -> bool,
+    {
+        let mut update = vec![(None, 0); self.cur_level + 1];
+        let mut current = None;
+        let mut rank = 0;
+
+        unsafe {
+            for lvl in (0..=self.cur_level).rev() {
+                while let Some(next) = self.next(current, lvl) {
+                    if precedes(self.nodes.key(next)) {
+                        rank += self.span(current, lvl);
+                        current = Some(next);
+                    } else {
+                        break;
+                    }
+                }
+                update[lvl] = (current, rank);
+            }
+
+            (self.next(current, 0), update)
+        }
+    }
+
+    pub fn find(&self, key: &K) -> Option<&V> {
+        let node = self.find_node(key)?;
+        unsafe { Some(self.nodes.value(node)) }
+    }
+
+    pub fn find_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = self.find_node(key)?;
+        unsafe { Some(self.nodes.value_mut(node)) }
+    }
+
+    fn find_node(&self, key: &K) -> Option<S::Id> {
+        unsafe {
+            let mut current = None;
+
+            for lvl in (0..=self.cur_level).rev() {
+                self.iter_node_on_level(&mut current, key, lvl);
+            }
+
+            current = self.next(current, 0);
+            if self.holds_key(current, key) {
+                current
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the entry at position `index` in key order.
+    pub fn get_by_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.node_entry(self.find_node_update_by_index(index).0)
+    }
+
+    /// Returns how many keys are strictly less than `key`.
+    pub fn rank(&self, key: &K) -> usize {
+        self.find_node_update(key).1[0].1
+    }
+
+    pub fn remove_by_index(&mut self, index: usize) -> Option<(K, V)> {
+        let (current, update) = self.find_node_update_by_index(index);
+        unsafe {
+            self.unlink_node(current?, &update);
+            Some(self.nodes.free(current?))
+        }
+    }
+
+    /// Same as `find_node_update`, but descends by position using the spans.
+    fn find_node_update_by_index(&self, index: usize) -> (Option<S::Id>, UpdateVec<S::Id>) {
+        let mut update = vec![(None, 0); self.cur_level + 1];
+        if index >= self.len {
+            return (None, update);
+        }
+
+        let mut current = None;
+        let mut rank = 0;
+
+        unsafe {
+            for lvl in (0..=self.cur_level).rev() {
+                while self.next(current, lvl).is_some() && rank + self.span(current, lvl) <= index {
+                    rank += self.span(current, lvl);
+                    current = self.next(current, lvl);
+                }
+                update[lvl] = (current, rank);
+            }
+
+            (self.next(current, 0), update)
+        }
+    }
+
+    fn node_iter(&self) -> NodeIter<'_, K, V, S> {
+        NodeIter {
+            nodes: &self.nodes,
+            front: self.head[0],
+            back: self.tail,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the node following `node` on `lvl`, `None` meaning the head.
+    unsafe fn next(&self, node: Option<S::Id>, lvl: usize) -> Option<S::Id> {
+        match node {
+            None => self.head[lvl],
+            Some(node) => self.nodes.next(node, lvl),
+        }
+    }
+
+    /// Points the `lvl` link of `node` at `to`. On level 0 this also sets
+    /// the back link of `to`, or the tail when `to` is `None`.
+    unsafe fn set_next(&mut self, node: Option<S::Id>, lvl: usize, to: Option<S::Id>) {
+        match node {
+            None => self.head[lvl] = to,
+            Some(node) => self.nodes.set_next(node, lvl, to),
+        }
+
+        if lvl == 0 {
+            match to {
+                None => self.tail = node,
+                Some(to) => self.nodes.set_prev(to, node),
+            }
+        }
+    }
+
+    /// Returns how many level 0 steps the `lvl` link of `node` skips.
+    unsafe fn span(&self, node: Option<S::Id>, lvl: usize) -> usize {
+        match node {
+            None => self.head_span[lvl],
+            Some(node) => self.nodes.span(node, lvl),
+        }
+    }
+
+    unsafe fn span_mut(&mut self, node: Option<S::Id>, lvl: usize) -> &mut usize {
+        match node {
+            None => &mut self.head_span[lvl],
+            Some(node) => self.nodes.span_mut(node, lvl),
+        }
+    }
+
+    unsafe fn iter_node_on_level(&self, current: &mut Option<S::Id>, search_key: &K, lvl: usize) {
+        self.iter_node_on_level_by(current, lvl, |key| self.compare(key, search_key).is_lt())
+    }
+
+    /// Moves `current` along `lvl` for as long as `precedes` holds for the next key.
+    unsafe fn iter_node_on_level_by<F>(&self, current: &mut Option<S::Id>, lvl: usize, precedes: F)
+    where
+        F: Fn(&K) -> bool,
+    {
+        while let Some(next) = self.next(*current, lvl) {
+            if precedes(self.nodes.key(next)) {
+                *current = Some(next);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Returns the last node for which `precedes` holds, `None` meaning the head.
+    unsafe fn descend_by<F>(&self, precedes: F) -> Option<S::Id>
+    where
+        F: Fn(&K) -> bool,
+    {
+        let mut current = None;
+        for lvl in (0..=self.cur_level).rev() {
+            self.iter_node_on_level_by(&mut current, lvl, &precedes);
+        }
+        current
+    }
+
+    /// Finds the first and last nodes with keys in `range`.
+    fn raw_range<R>(&self, range: R) -> RawRange<S::Id>
+    where
+        R: RangeBounds<K>,
+    {
+        unsafe {
+            let front = match range.start_bound() {
+                Bound::Included(start) => self.descend_by(|key| self.compare(key, start).is_lt()),
+                Bound::Excluded(start) => self.descend_by(|key| self.compare(key, start).is_le()),
+                Bound::Unbounded => None,
+            };
+            let front = self.next(front, 0);
+
+            let back = match range.end_bound() {
+                Bound::Included(end) => self.descend_by(|key| self.compare(key, end).is_le()),
+                Bound::Excluded(end) => self.descend_by(|key| self.compare(key, end).is_lt()),
+                Bound::Unbounded => self.tail,
+            };
+
+            match (front, back) {
+                (Some(f), Some(b))
+                    if self.compare(self.nodes.key(f), self.nodes.key(b)).is_le() =>
+                {
+                    RawRange { front, back }
+                }
+                _ => RawRange {
+                    front: None,
+                    back: None,
+                },
+            }
+        }
+    }
+
+    pub fn range<R>(&self, range: R) -> Range<'_, K, V, S>
+    where
+        R: RangeBounds<K>,
+    {
+        Range {
+            nodes: &self.nodes,
+            inner: self.raw_range(range),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn range_mut<R>(&mut self, range: R) -> RangeMut<'_, K, V, S>
+    where
+        R: RangeBounds<K>,
+    {
+        RangeMut {
+            inner: self.raw_range(range),
+            nodes: NonNull::from(&mut self.nodes),
+            marker: PhantomData,
+        }
+    }
+
+    /// Iterates over the entries whose keys are not less than `key`.
+    pub fn lower_bound(&self, key: &K) -> Range<'_, K, V, S> {
+        self.range((Bound::Included(key), Bound::Unbounded))
+    }
+
+    /// Iterates over the entries whose keys are greater than `key`.
+    pub fn upper_bound(&self, key: &K) -> Range<'_, K, V, S> {
+        self.range((Bound::Excluded(key), Bound::Unbounded))
+    }
+
+    /// Returns the entry with the greatest key less than or equal to `key`.
+    pub fn floor(&self, key: &K) -> Option<(&K, &V)> {
+        let node = unsafe { self.descend_by(|k| self.compare(k, key).is_le()) };
+        self.node_entry(node)
+    }
+
+    /// Returns the entry with the least key greater than or equal to `key`.
+    pub fn ceiling(&self, key: &K) -> Option<(&K, &V)> {
+        let node = unsafe { self.next(self.descend_by(|k| self.compare(k, key).is_lt()), 0) };
+        self.node_entry(node)
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.node_entry(self.head[0])
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.node_entry(self.tail)
+    }
+
+    /// Unlinks the first node, which is only ever referenced by the head.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let node = self.head[0]?;
+
+        unsafe {
+            let height = self.nodes.level(node) + 1;
+            for lvl in 0..=self.cur_level {
+                if lvl < height {
+                    self.set_next(None, lvl, self.nodes.next(node, lvl));
+                    self.head_span[lvl] += self.nodes.span(node, lvl);
+                }
+                self.head_span[lvl] -= 1;
+            }
+
+            self.shrink_cur_level();
+            self.len -= 1;
+            Some(self.nodes.free(node))
+        }
+    }
+
+    /// Unlinks the last node, descending only to collect its predecessors.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let last = self.tail?;
+
+        unsafe {
+            let (_, update) = self.find_node_update(self.nodes.key(last));
+            self.unlink_node(last, &update);
+            Some(self.nodes.free(last))
+        }
+    }
+
+    /// Moves every entry of `other` into this list, leaving `other` empty.
+    /// Values from `other` replace the ones stored under the same keys.
+    ///
+    /// When all keys of `other` are greater than the keys of this list, its
+    /// towers are spliced on in O(log n), or moved over in O(m) for storages
+    /// whose node handles are local to a list. Otherwise both lists are
+    /// merged in a single pass, keeping the height of every tower.
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
+        }
+
+        self.raise_max_level(other.max_level);
+        let follows = match (self.last(), other.first()) {
+            (Some((last, _)), Some((first, _))) => self.compare(first, last).is_gt(),
+            _ => true,
+        };
+
+        unsafe {
+            if follows {
+                self.splice_back(other)
+            } else {
+                self.merge_nodes(other)
+            }
+        }
+    }
+
+    /// Splits the list in two at `key`: entries with keys not less than `key`
+    /// move into the returned list, which keeps the same settings.
+    pub fn split_off(&mut self, key: &K) -> Self
+    where
+        C: Clone,
+    {
+        let (first, update) = self.find_node_update(key);
+        let split = update[0].1;
+
+        let mut other = Self::with_seed_and_comparator_in(
+            self.fraction,
+            self.max_level,
+            self.rng.next_u64(),
+            self.comparator.clone(),
+            S::default(),
+        );
+        other.capacity = self.capacity;
+
+        unsafe {
+            if S::GLOBAL_IDS {
+                other.cur_level = self.cur_level;
+                other.len = self.len - split;
+                for (lvl, &(each, rank)) in update.iter().enumerate() {
+                    other.set_next(None, lvl, self.next(each, lvl));
+                    other.head_span[lvl] = rank + self.span(each, lvl) - split;
+                }
+                if other.len > 0 {
+                    other.tail = self.tail;
+                }
+            } else {
+                other.adopt_chain(&mut self.nodes, first);
+            }
+
+            for (lvl, &(each, rank)) in update.iter().enumerate() {
+                self.set_next(each, lvl, None);
+                *self.span_mut(each, lvl) = split - rank;
+            }
+        }
+
+        self.len = split;
+        self.shrink_cur_level();
+        other.shrink_cur_level();
+        other
+    }
+
+    /// Links the towers of `other` after the last node of this list.
+    unsafe fn splice_back(&mut self, other: &mut Self) {
+        if !S::GLOBAL_IDS {
+            let first = other.detach();
+            self.adopt_chain(&mut other.nodes, first);
+            other.nodes.clear(None);
+            return;
         }
-    }
 
-    pub fn with_fraction(fraction: f64) -> Self {
-        Self::new(fraction, MAX_LEVEL)
-    }
+        let (_, tails) = self.find_node_update_by(|_| true);
 
-    pub fn clear(&mut self) {
-        *self = Self::new(self.fraction, self.max_level)
-    }
+        for lvl in 0..=self.cur_level.max(other.cur_level) {
+            // Levels above `cur_level` end right at the head
+            let (tail, rank) = tails.get(lvl).copied().unwrap_or((None, 0));
+            if lvl <= other.cur_level {
+                self.set_next(tail, lvl, other.head[lvl]);
+                *self.span_mut(tail, lvl) = self.len - rank + other.head_span[lvl];
+            } else {
+                *self.span_mut(tail, lvl) += other.len;
+            }
+        }
 
-    pub fn is_empty(&self) -> bool {
-        self.len == 0
+        self.cur_level = self.cur_level.max(other.cur_level);
+        self.len += other.len;
+        self.tail = other.tail;
+        other.detach();
     }
 
-    pub fn len(&self) -> usize {
-        self.len
-    }
+    /// Moves the nodes chained on level 0 from `first` out of `from` and
+    /// links them after the last node of this list, keeping their heights.
+    unsafe fn adopt_chain(&mut self, from: &mut S, first: Option<S::Id>) {
+        let (_, mut tails) = self.find_node_update_by(|_| true);
+        let mut node = first;
 
-    pub fn cur_level(&self) -> usize {
-        self.cur_level
-    }
+        while let Some(each) = node {
+            node = from.next(each, 0);
+            let each = self.nodes.adopt(from, each);
+            self.push_back_node(&mut tails, each);
+        }
 
-    pub fn max_level(&self) -> usize {
-        self.max_level
+        self.close_tails(&tails);
     }
 
-    pub fn insert(&mut self, key: usize, value: V) -> Result<(), &str> {
-        unsafe {
-            let (current, mut update) = self.find_node_update(key);
-            if current.is_none() || current.unwrap().as_ref().key != key {
-                let level = self.random_level();
-
-                if level > self.cur_level {
-                    for each in update.iter_mut().take(level + 1).skip(self.cur_level + 1) {
-                        *each = Some(self.head)
-                    }
-                    self.cur_level = level
-                }
+    /// Relinks the nodes of both lists in key order, freeing the nodes
+    /// of this list whose keys `other` holds as well.
+    unsafe fn merge_nodes(&mut self, other: &mut Self) {
+        let mut left = self.detach();
+        let mut right = other.detach();
+        let mut tails = Vec::new();
 
-                let node = SkipNode::new(key, value, level);
-                let mut node_ptr = NonNull::from(Box::leak(Box::new(node)));
+        loop {
+            let order = match (left, right) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(l), Some(r)) => self.compare(self.nodes.key(l), other.nodes.key(r)),
+            };
 
-                for (i, each) in update.iter().enumerate().take(level + 1) {
-                    let each = each.unwrap().as_mut();
-                    node_ptr.as_mut().next[i] = each.next[i];
-                    each.next[i] = Some(node_ptr);
+            let node = if order == Ordering::Less {
+                let node = left.unwrap();
+                left = self.nodes.next(node, 0);
+                node
+            } else {
+                if order == Ordering::Equal {
+                    let node = left.unwrap();
+                    left = self.nodes.next(node, 0);
+                    self.nodes.free(node);
                 }
+                let node = right.unwrap();
+                right = other.nodes.next(node, 0);
+                self.nodes.adopt(&mut other.nodes, node)
+            };
 
-                self.len += 1;
-                Ok(())
-            } else {
-                Err("provided key already exists")
-            }
+            self.push_back_node(&mut tails, node);
         }
+
+        self.close_tails(&tails);
+        other.nodes.clear(None);
     }
 
-    fn random_level(&self) -> usize {
-        let mut rng = thread_rng();
-        let mut level = 0;
+    /// Empties the list without freeing its nodes, returning the first one.
+    fn detach(&mut self) -> Option<S::Id> {
+        let first = self.head[0];
+        self.head.fill(None);
+        self.head_span.fill(0);
+        self.tail = None;
+        self.cur_level = 0;
+        self.len = 0;
+        first
+    }
 
-        while rng.gen_range(0.0..1.0) < self.fraction && level < self.max_level {
-            level += 1
+    fn finger(&self) -> Finger<S::Id> {
+        Finger {
+            path: vec![(None, 0); self.cur_level + 1],
         }
-
-        level
     }
 
-    pub fn pop(&mut self, key: usize) -> Option<V> {
-        Some(self.pop_node(key)?.value)
+    fn finger_current(&self, finger: &Finger<S::Id>) -> Option<S::Id> {
+        unsafe { self.next(finger.path[0].0, 0) }
     }
 
-    fn pop_node(&mut self, key: usize) -> Option<Box<SkipNode<usize, V>>> {
+    /// Moves `finger` to the first entry for which `precedes` does not hold.
+    ///
+    /// Levels are climbed from the bottom only until the path on one of them
+    /// already brackets the target, so a target `d` entries away takes
+    /// O(log d) steps instead of a whole descent from the head.
+    fn seek_by<F>(&self, finger: &mut Finger<S::Id>, precedes: F)
+    where
+        F: Fn(&K) -> bool,
+    {
         unsafe {
-            let (current, update) = self.find_node_update(key);
-            let current_key = current?.as_ref().key;
+            let before = |node: Option<S::Id>| match node {
+                None => true,
+                Some(node) => precedes(self.nodes.key(node)),
+            };
+            let path = &mut finger.path;
+            let brackets = |path: &UpdateVec<S::Id>, lvl: usize| {
+                let (node, _) = path[lvl];
+                let next = self.next(node, lvl);
+                before(node) && (next.is_none() || !before(next))
+            };
 
-            if current_key != key {
-                return None;
+            let mut top = 0;
+            while top <= self.cur_level && !brackets(path, top) {
+                top += 1;
             }
 
-            for (i, each) in update.iter().enumerate().take(self.cur_level + 1) {
-                let each = each.unwrap().as_mut();
-                if each.next[i] != current {
-                    break;
+            let mut current = path.get(top).copied().unwrap_or((None, 0));
+            for lvl in (0..top).rev() {
+                // Going forward, the old path may already be further along
+                let (node, rank) = path[lvl];
+                if rank > current.1 && before(node) {
+                    current = (node, rank);
+                }
+
+                while let Some(next) = self.next(current.0, lvl) {
+                    if precedes(self.nodes.key(next)) {
+                        current = (Some(next), current.1 + self.span(current.0, lvl));
+                    } else {
+                        break;
+                    }
                 }
-                each.next[i] = current.unwrap().as_ref().next[i]
+                path[lvl] = current;
             }
+        }
+    }
 
-            while self.cur_level > 0 && self.head.as_ref().next[self.cur_level].is_none() {
-                self.cur_level -= 1;
+    fn finger_next(&self, finger: &mut Finger<S::Id>) {
+        if let Some(node) = self.finger_current(finger) {
+            let rank = finger.path[0].1 + 1;
+            let height = unsafe { self.nodes.level(node) + 1 };
+            for each in finger.path.iter_mut().take(height) {
+                *each = (Some(node), rank);
             }
+        }
+    }
 
-            self.len -= 1;
-            Some(Box::from_raw(current?.as_ptr()))
+    fn finger_prev(&self, finger: &mut Finger<S::Id>) {
+        if let Some(prev) = finger.path[0].0 {
+            let key = unsafe { self.nodes.key(prev) };
+            self.seek_by(finger, |k| self.compare(k, key).is_lt());
         }
     }
 
-    fn find_node_update(&self, key: usize) -> (MaybeNone<SkipNode<usize, V>>, UpdateVec<V>) {
-        let mut update = vec![None; self.max_level + 1];
-        let mut current = Some(self.head);
+    /// Iterates over the entries of both lists, see `Union`.
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, K, V, C, S> {
+        Union {
+            inner: self.merge_iter(other),
+        }
+    }
 
-        unsafe {
-            for lvl in (0..=self.cur_level).rev() {
-                Self::iter_node_on_level(&mut current, key, lvl);
-                update[lvl] = current;
-            }
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, K, V, C, S> {
+        Intersection {
+            inner: self.merge_iter(other),
+        }
+    }
 
-            current = current.unwrap().as_ref().next[0];
-            (current, update)
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, K, V, C, S> {
+        Difference {
+            inner: self.merge_iter(other),
         }
     }
 
-    pub fn find(&self, key: usize) -> Option<&V> {
-        unsafe {
-            let mut current = Some(self.head);
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> SymmetricDifference<'a, K, V, C, S> {
+        SymmetricDifference {
+            inner: self.merge_iter(other),
+        }
+    }
 
-            for lvl in (0..=self.cur_level).rev() {
-                Self::iter_node_on_level(&mut current, key, lvl);
-            }
+    fn merge_iter<'a>(&'a self, other: &'a Self) -> MergeIter<'a, K, V, C, S> {
+        MergeIter {
+            left: self.iter().peekable(),
+            right: other.iter().peekable(),
+            comparator: &self.comparator,
+        }
+    }
 
-            current = current.unwrap().as_ref().next[0];
+    fn node_entry(&self, node: Option<S::Id>) -> Option<(&K, &V)> {
+        node.map(|node| unsafe { (self.nodes.key(node), self.nodes.value(node)) })
+    }
 
-            let current_ref = current?.as_ref();
-            if current_ref.key == key {
-                Some(&current_ref.value)
-            } else {
-                None
-            }
+    pub fn iter(&self) -> Iter<'_, K, V, S> {
+        Iter {
+            inner: self.node_iter(),
         }
     }
 
-    fn node_iter(&self) -> NodeIter<V> {
-        NodeIter {
-            current: unsafe { self.head.as_ref().next[0] },
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V, S> {
+        IterMut {
+            nodes: NonNull::from(&mut self.nodes),
+            front: self.head[0],
+            back: self.tail,
             len: self.len,
             marker: PhantomData,
         }
     }
 
-    unsafe fn iter_node_on_level(
-        current: &mut MaybeNone<SkipNode<usize, V>>,
-        search_key: usize,
-        lvl: usize,
-    ) {
-        while let Some(next) = current.unwrap().as_ref().next[lvl] {
-            if next.as_ref().key < search_key {
-                *current = Some(next);
-            } else {
-                break;
-            }
+    /// Returns a cursor at the first entry.
+    pub fn cursor_front(&self) -> Cursor<'_, K, V, C, S> {
+        Cursor {
+            list: self,
+            finger: self.finger(),
+        }
+    }
+
+    /// Returns a cursor at the first entry that can modify the list.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, K, V, C, S> {
+        CursorMut {
+            finger: self.finger(),
+            list: self,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V, S> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V, S> {
+        Values { inner: self.iter() }
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V, S> {
+        ValuesMut {
+            inner: self.iter_mut(),
         }
     }
 
     pub fn node_ptrs(&self) -> usize {
-        let mut node_ptrs = 0;
+        let mut node_ptrs = self.head.iter().filter(|next| next.is_some()).count();
 
         for node in self.node_iter() {
-            for ptr in &node.next {
-                if ptr.is_some() {
-                    node_ptrs += 1;
+            unsafe {
+                for lvl in 0..=self.nodes.level(node) {
+                    if self.nodes.next(node, lvl).is_some() {
+                        node_ptrs += 1;
+                    }
                 }
             }
         }
+
+        node_ptrs
+    }
+
+    /// Collects the level histogram, search cost and footprint of the list.
+    /// Takes O(n log n), as every key is looked up once.
+    pub fn stats(&self) -> Stats {
+        let mut levels = vec![0; self.cur_level + 1];
+        let mut search_path = 0;
+        let mut memory = size_of::<Self>()
+            + self.head.capacity() * size_of::<Option<S::Id>>()
+            + self.head_span.capacity() * size_of::<usize>()
+            + self.nodes.memory();
+
+        for node in self.node_iter() {
+            unsafe {
+                levels[self.nodes.level(node)] += 1;
+                search_path += self.search_path_len(self.nodes.key(node));
+                memory += self.nodes.node_memory(node);
+            }
+        }
+
+        let avg_search_path = if self.len == 0 {
+            0.0
+        } else {
+            search_path as f64 / self.len as f64
+        };
+
+        Stats {
+            len: self.len,
+            cur_level: self.cur_level,
+            max_level: self.max_level,
+            levels,
+            pointers: self.node_ptrs(),
+            avg_search_path,
+            memory,
+        }
+    }
+
+    /// Counts the links followed and levels descended while looking up `key`.
+    fn search_path_len(&self, key: &K) -> usize {
+        let mut current = None;
+        let mut steps = 0;
+
         unsafe {
-            for ptr in &self.head.as_ref().next {
-                if ptr.is_some() {
-                    node_ptrs += 1;
+            for lvl in (0..=self.cur_level).rev() {
+                while let Some(next) = self.next(current, lvl) {
+                    if self.compare(self.nodes.key(next), key).is_lt() {
+                        current = Some(next);
+                        steps += 1;
+                    } else {
+                        break;
+                    }
                 }
+                // Moving down, or onto the key itself from level 0
+                steps += 1;
             }
         }
 
-        node_ptrs
+        steps
     }
+
+    /// Writes the tower layout as a Graphviz DOT graph. Every node becomes
+    /// a record with one field per level, and every link is labelled with
+    /// its span. Render it with `dot -Tsvg`.
+    pub fn write_dot<W: io::Write>(&self, out: &mut W) -> io::Result<()>
+    where
+        K: Debug,
+    {
+        let ids: HashMap<_, _> = self
+            .node_iter()
+            .enumerate()
+            .map(|(i, node)| (node, i))
+            .collect();
+        let target = |next: Option<S::Id>| match next {
+            None => String::from("nil"),
+            Some(node) => format!("n{}", ids[&node]),
+        };
+        let record = |top: usize, caption: &str| {
+            let mut fields: Vec<String> = (1..=top).rev().map(|lvl| format!("<l{lvl}> ")).collect();
+            fields.push(format!("<l0> {caption}"));
+            fields.join("|")
+        };
+
+        writeln!(out, "digraph SkipList {{")?;
+        writeln!(out, "    rankdir=LR;")?;
+        writeln!(out, "    node [shape=record];")?;
+        writeln!(
+            out,
+            "    head [label=\"{}\"];",
+            record(self.cur_level, "head")
+        )?;
+        writeln!(
+            out,
+            "    nil [label=\"{}\"];",
+            record(self.cur_level, "nil")
+        )?;
+
+        for (i, node) in self.node_iter().enumerate() {
+            let (key, level) = unsafe { (self.nodes.key(node), self.nodes.level(node)) };
+            let key = escape_record(&format!("{key:?}"));
+            writeln!(out, "    n{i} [label=\"{}\"];", record(level, &key))?;
+        }
+
+        for lvl in 0..=self.cur_level {
+            let (next, span) = (self.head[lvl], self.head_span[lvl]);
+            writeln!(
+                out,
+                "    head:l{lvl} -> {}:l{lvl} [label={span}];",
+                target(next)
+            )?;
+        }
+        for (i, node) in self.node_iter().enumerate() {
+            for lvl in 0..=unsafe { self.nodes.level(node) } {
+                let (next, span) =
+                    unsafe { (self.nodes.next(node, lvl), self.nodes.span(node, lvl)) };
+                writeln!(
+                    out,
+                    "    n{i}:l{lvl} -> {}:l{lvl} [label={span}];",
+                    target(next)
+                )?;
+            }
+        }
+
+        writeln!(out, "}}")
+    }
+}
+
+/// Returns the maximum level a list in capacity mode keeps for `capacity`,
+/// `log(capacity) / log(1 / fraction)` rounded up.
+pub(crate) fn level_for(capacity: usize, fraction: f64) -> usize {
+    let level = ((capacity.max(1) as f64).ln() / (1.0 / fraction).ln()).ceil() as usize;
+    level.min(MAX_LEVEL)
+}
+
+/// Escapes the characters that have a meaning inside a DOT record label.
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '"' | '{' | '}' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
-impl<V> Default for SkipList<V>
+impl<K, V, C, S> Default for SkipList<K, V, C, S>
 where
-    V: Default,
+    C: Compare<K> + Default,
+    S: Storage<K, V>,
 {
     fn default() -> Self {
-        Self::new(0.5, MAX_LEVEL)
+        Self::with_capacity_and_comparator_in(0, 0.5, C::default(), S::default())
+    }
+}
+
+impl<K, V, C, S> IntoIterator for SkipList<K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, C, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<'a, K, V, C, S> IntoIterator for &'a SkipList<K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, C, S> IntoIterator for &'a mut SkipList<K, V, C, S>
+where
+    C: Compare<K>,
+    S: Storage<K, V>,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, C, S> FromIterator<(K, V)> for SkipList<K, V, C, S>
+where
+    C: Compare<K> + Default,
+    S: Storage<K, V>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut list = Self::with_capacity_and_comparator_in(
+            iter.size_hint().0,
+            0.5,
+            C::default(),
+            S::default(),
+        );
+        list.extend(iter);
+        list
     }
 }
 
-impl<V> Clone for SkipList<V>
+impl<K, V, C, S> Extend<(K, V)> for SkipList<K, V, C, S>
 where
-    V: Default + Clone,
+    C: Compare<K>,
+    S: Storage<K, V>,
 {
+    /// Keys that are already present get their value replaced.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert_or_replace(key, value);
+        }
+    }
+}
+
+impl<K, V, C, S> Clone for SkipList<K, V, C, S>
+where
+    K: Clone,
+    V: Clone,
+    C: Compare<K> + Clone,
+    S: Storage<K, V>,
+{
+    /// Copies every node together with its tower height, so the clone has
+    /// exactly the same layout as the original. A `Shared` list hands its
+    /// nodes to the clone instead, in O(1).
     fn clone(&self) -> Self {
-        let mut clone = SkipList::new(self.fraction, self.max_level);
+        let mut clone = Self::with_seed_and_comparator_in(
+            self.fraction,
+            self.max_level,
+            0,
+            self.comparator.clone(),
+            S::default(),
+        );
+        clone.rng = self.rng.clone();
+        clone.capacity = self.capacity;
+
+        // Storages that keep the handles can be copied wholesale
+        if let Some(nodes) = self.nodes.try_clone() {
+            clone.nodes = nodes;
+            clone.head.clone_from(&self.head);
+            clone.head_span.clone_from(&self.head_span);
+            clone.tail = self.tail;
+            clone.cur_level = self.cur_level;
+            clone.len = self.len;
+            return clone;
+        }
 
+        let mut tails = Vec::new();
         for each in self.node_iter() {
-            clone.insert(each.key, each.value.clone()).unwrap_or(());
+            unsafe {
+                let (key, value) = (self.nodes.key(each), self.nodes.value(each));
+                let node = clone
+                    .nodes
+                    .alloc(key.clone(), value.clone(), self.nodes.level(each));
+                clone.push_back_node(&mut tails, node);
+            }
         }
 
+        unsafe { clone.close_tails(&tails) };
         clone
     }
 }
 
-impl<V> Debug for SkipList<V>
+impl<K, V, C, S> Debug for SkipList<K, V, C, S>
 where
-    V: Default,
+    K: Debug,
+    C: Compare<K>,
+    S: Storage<K, V>,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         for lvl in 0..self.cur_level + 1 {
             write!(f, "Lv{lvl} - ")?;
-            let mut node = unsafe { (self.head.as_ref()).next[lvl] };
-            while node.is_some() {
-                let node_ref = unsafe { node.unwrap().as_ref() };
-                write!(f, "{} ", node_ref.key)?;
-                node = node_ref.next[lvl];
+            let mut node = self.head[lvl];
+            while let Some(each) = node {
+                unsafe {
+                    write!(f, "{:?} ", self.nodes.key(each))?;
+                    node = self.nodes.next(each, lvl);
+                }
             }
             writeln!(f)?;
         }
@@ -279,24 +2523,612 @@ where
     }
 }
 
-impl<V> Drop for SkipList<V>
+impl<K, V, C, S> Drop for SkipList<K, V, C, S>
 where
-    V: Default,
+    C: Compare<K>,
+    S: Storage<K, V>,
 {
     fn drop(&mut self) {
-        unsafe {
-            let mut key = self.head.as_ref().next[0].unwrap().as_ref().key;
-            while !self.is_empty() {
-                if let Some(node) = self.pop_node(key) {
-                    if let Some(next) = node.next[0] {
-                        key = next.as_ref().key;
+        self.clear()
+    }
+}
+
+// The iterators hand out values of different nodes while holding the storage
+// through a raw pointer, so they are as thread-safe as `&mut S` would be.
+unsafe impl<K, V, S: Storage<K, V> + Send> Send for IterMut<'_, K, V, S> {}
+unsafe impl<K, V, S: Storage<K, V> + Sync> Sync for IterMut<'_, K, V, S> {}
+
+unsafe impl<K, V, S: Storage<K, V> + Send> Send for RangeMut<'_, K, V, S> {}
+unsafe impl<K, V, S: Storage<K, V> + Sync> Sync for RangeMut<'_, K, V, S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::SkipList;
+    use crate::error::SkipListError;
+    use crate::lists::arena_skip_list::Arena;
+    use crate::lists::compare::{Compare, Descending, Natural};
+    use crate::lists::shared_skip_list::Shared;
+    use crate::lists::storage::{Boxed, Nodes, Storage};
+    use crate::test_support::{check, random_range, Seeded, KEYS, MODEL_SEEDS, MODEL_STEPS};
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use std::cmp::Reverse;
+    use std::collections::BTreeMap;
+    use std::mem::size_of;
+    use std::ops::{Bound, RangeBounds};
+
+    type List<S = Boxed<u8, String>> = SkipList<u8, String, Natural, S>;
+    type Model = BTreeMap<u8, String>;
+
+    /// Moves a cursor around the list, checking its position against the
+    /// model and changing the list through it.
+    fn cursor_steps<S: Storage<u8, String>>(
+        rng: &mut Xoshiro256Plus,
+        list: &mut List<S>,
+        model: &mut Model,
+        step: usize,
+    ) {
+        let mut cursor = list.cursor_front_mut();
+        let mut pos = 0;
+
+        for _ in 0..rng.gen_range(1..8) {
+            let key = rng.gen_range(0..KEYS);
+            match rng.gen_range(0..6) {
+                0 => {
+                    pos = model.range(..key).count();
+                    assert_eq!(cursor.seek(&key), model.contains_key(&key));
+                }
+                1 => {
+                    cursor.move_next();
+                    pos = (pos + 1).min(model.len());
+                }
+                2 => {
+                    cursor.move_prev();
+                    pos = pos.saturating_sub(1);
+                }
+                3 => {
+                    let current = model.keys().nth(pos).copied();
+                    let next = match current {
+                        Some(current) => model.range(current + 1..).next(),
+                        None => model.iter().next(),
+                    };
+                    let fits = current.is_none_or(|current| key > current)
+                        && next.is_none_or(|(next, _)| key < *next);
+                    let result = cursor.insert_after(key, step.to_string());
+                    if fits {
+                        assert_eq!(result, Ok(()));
+                        model.insert(key, step.to_string());
+                        if current.is_none() {
+                            pos += 1;
+                        }
+                    } else if current == Some(key) || next.map(|(next, _)| *next) == Some(key) {
+                        assert_eq!(result, Err(SkipListError::DuplicateKey));
+                    } else {
+                        assert_eq!(result, Err(SkipListError::UnorderedKey));
+                    }
+                }
+                4 => {
+                    let current = model.keys().nth(pos).copied();
+                    let removed = current.map(|key| (key, model.remove(&key).unwrap()));
+                    assert_eq!(cursor.remove_current(), removed);
+                }
+                _ => {
+                    if let Some(value) = cursor.value_mut() {
+                        value.push('!');
+                        model.values_mut().nth(pos).unwrap().push('!');
+                    }
+                }
+            }
+            assert_eq!(cursor.index(), pos);
+            assert_eq!(cursor.entry(), model.iter().nth(pos));
+        }
+    }
+
+    /// Runs a list on storage `S` against a `BTreeMap`, so that every storage
+    /// goes through the same operations.
+    fn matches_btree_map<S: Storage<u8, String>>() {
+        for seed in 0..MODEL_SEEDS {
+            let mut rng = Xoshiro256Plus::seed_from_u64(seed);
+            let mut list = List::<S>::for_seed(seed);
+            let mut model = Model::new();
+            let mut snapshots: Vec<(List<S>, Model)> = Vec::new();
+
+            for step in 0..MODEL_STEPS {
+                let key = rng.gen_range(0..KEYS);
+                let value = step.to_string();
+                match rng.gen_range(0..16) {
+                    0..=2 => {
+                        let expected = match model.contains_key(&key) {
+                            true => Err(SkipListError::DuplicateKey),
+                            false => Ok(()),
+                        };
+                        assert_eq!(list.insert(key, value.clone()), expected);
+                        model.entry(key).or_insert(value);
+                    }
+                    3 => assert_eq!(
+                        list.insert_or_replace(key, value.clone()),
+                        model.insert(key, value)
+                    ),
+                    4..=5 => assert_eq!(list.pop(&key), model.remove(&key)),
+                    6 => match rng.gen() {
+                        true => assert_eq!(list.pop_first(), model.pop_first()),
+                        false => assert_eq!(list.pop_last(), model.pop_last()),
+                    },
+                    7 => {
+                        let index = rng.gen_range(0..=model.len());
+                        let key = model.keys().nth(index).copied();
+                        let removed = key.map(|key| (key, model.remove(&key).unwrap()));
+                        assert_eq!(list.remove_by_index(index), removed);
+                    }
+                    8 => {
+                        list.entry(key)
+                            .and_modify(|value| value.push('!'))
+                            .or_insert_with(|| value.clone());
+                        model
+                            .entry(key)
+                            .and_modify(|value| value.push('!'))
+                            .or_insert(value);
+                    }
+                    9 => {
+                        let modulus = rng.gen_range(2..5);
+                        list.retain(|key, _| key % modulus != 0);
+                        model.retain(|key, _| key % modulus != 0);
+                    }
+                    10 => {
+                        let range = random_range(&mut rng);
+                        assert!(list.range(range).eq(model.range(range)));
+                        assert!(list.range(range).rev().eq(model.range(range).rev()));
+                        assert_eq!(list.floor(&key), model.range(..=key).next_back());
+                        assert_eq!(list.ceiling(&key), model.range(key..).next());
+
+                        let taken = rng.gen_range(0..4);
+                        let drained: Vec<_> = list.drain_range(range).take(taken).collect();
+                        let keys: Vec<_> = model.range(range).map(|(key, _)| *key).collect();
+                        let mut removed = keys
+                            .into_iter()
+                            .map(|key| (key, model.remove(&key).unwrap()))
+                            .collect::<Vec<_>>();
+                        removed.truncate(taken);
+                        assert_eq!(drained, removed);
+                    }
+                    11 => {
+                        let mut other = list.split_off(&key);
+                        let other_model = model.split_off(&key);
+                        check(&list, &model);
+                        check(&other, &other_model);
+
+                        if rng.gen() {
+                            list.append(&mut other);
+                            model.extend(other_model);
+                        } else {
+                            other.append(&mut list);
+                            list = other;
+                            model.extend(other_model);
+                        }
+                    }
+                    12 => {
+                        let entries: Vec<_> = (0..rng.gen_range(0..6))
+                            .map(|_| (rng.gen_range(0..KEYS), value.clone()))
+                            .collect::<BTreeMap<_, _>>()
+                            .into_iter()
+                            .collect();
+                        if entries.len() > 1 && rng.gen_ratio(1, 4) {
+                            // Out of order, so the list must stay as it was
+                            let reversed = entries.into_iter().rev();
+                            assert_eq!(
+                                list.bulk_insert(reversed),
+                                Err(SkipListError::UnorderedKey)
+                            );
+                        } else if rng.gen() {
+                            assert_eq!(list.bulk_insert(entries.clone()), Ok(()));
+                            model.extend(entries);
+                        } else {
+                            let mut other = List::with_seed_in(0.5, 8, step as u64, S::default());
+                            other.extend(entries.clone());
+                            list.append(&mut other);
+                            assert!(other.is_empty());
+                            model.extend(entries);
+                        }
+                    }
+                    13 => cursor_steps(&mut rng, &mut list, &mut model, step),
+                    14 => {
+                        // Kept around to check that later writes leave it alone
+                        let snapshot = list.clone();
+                        if snapshots.len() < 4 {
+                            snapshots.push((snapshot, model.clone()));
+                        } else {
+                            snapshots[step % 4] = (snapshot, model.clone());
+                        }
+                    }
+                    _ => match rng.gen_range(0..8) {
+                        0 => {
+                            list.clear();
+                            model.clear();
+                        }
+                        1 => list.rebalance(),
+                        2 => {
+                            let clone = list.clone();
+                            list.insert_or_replace(key, value.clone());
+                            check(&clone, &model);
+                            list = clone;
+                        }
+                        _ => {
+                            list.values_mut().for_each(|value| value.push('?'));
+                            model.values_mut().for_each(|value| value.push('?'));
+                        }
+                    },
+                }
+                check(&list, &model);
+            }
+
+            for (snapshot, model) in &snapshots {
+                check(snapshot, model);
+            }
+            let taken = rng.gen_range(0..=model.len());
+            assert!(list
+                .into_iter()
+                .rev()
+                .take(taken)
+                .eq(model.into_iter().rev().take(taken)));
+        }
+    }
+
+    #[test]
+    fn boxed_list_matches_btree_map() {
+        matches_btree_map::<Boxed<u8, String>>();
+    }
+
+    #[test]
+    fn arena_list_matches_btree_map() {
+        matches_btree_map::<Arena<u8, String>>();
+    }
+
+    #[test]
+    fn shared_list_matches_btree_map() {
+        matches_btree_map::<Shared<u8, String>>();
+    }
+
+    fn entry<'a, O>((_, (key, value)): (&'a O, &'a (u8, String))) -> (&'a u8, &'a String) {
+        (key, value)
+    }
+
+    /// Runs a list ordered by `comparator` against a `BTreeMap` keyed by
+    /// `order`, which has to sort keys the same way.
+    fn matches_ordered_model<C, O>(comparator: C, order: impl Fn(u8) -> O)
+    where
+        C: Compare<u8> + Clone,
+        O: Ord,
+    {
+        for seed in 0..MODEL_SEEDS {
+            let mut rng = Xoshiro256Plus::seed_from_u64(seed);
+            let mut list = SkipList::with_seed_and_comparator(0.5, 8, seed, comparator.clone());
+            let mut model = BTreeMap::new();
+
+            for step in 0..MODEL_STEPS {
+                let key = rng.gen_range(0..KEYS);
+                let value = step.to_string();
+                match rng.gen_range(0..8) {
+                    0..=2 => assert_eq!(
+                        list.insert_or_replace(key, value.clone()),
+                        model.insert(order(key), (key, value)).map(|(_, old)| old)
+                    ),
+                    3 => assert_eq!(list.pop(&key), model.remove(&order(key)).map(|(_, v)| v)),
+                    4 => match rng.gen() {
+                        true => assert_eq!(list.pop_first(), model.pop_first().map(|(_, e)| e)),
+                        false => assert_eq!(list.pop_last(), model.pop_last().map(|(_, e)| e)),
+                    },
+                    5 => {
+                        let floor = model.range(..=order(key)).next_back().map(entry);
+                        let ceiling = model.range(order(key)..).next().map(entry);
+                        assert_eq!(list.floor(&key), floor);
+                        assert_eq!(list.ceiling(&key), ceiling);
+                    }
+                    6 => {
+                        let other = rng.gen_range(0..KEYS);
+                        let (start, end) = match order(key) <= order(other) {
+                            true => (key, other),
+                            false => (other, key),
+                        };
+                        let range = match (start == end, rng.gen()) {
+                            (false, true) => (Bound::Excluded(start), Bound::Excluded(end)),
+                            _ => (Bound::Included(start), Bound::Included(end)),
+                        };
+                        let model_range = || (range.0.map(&order), range.1.map(&order));
+                        assert!(list.range(range).eq(model.range(model_range()).map(entry)));
+                        assert!(list
+                            .range(range)
+                            .rev()
+                            .eq(model.range(model_range()).rev().map(entry)));
                     }
+                    _ => {
+                        let mut cursor = list.cursor_front_mut();
+                        assert_eq!(cursor.seek(&key), model.contains_key(&order(key)));
+                        assert_eq!(cursor.index(), model.range(..order(key)).count());
+                        assert_eq!(cursor.entry(), model.range(order(key)..).next().map(entry));
+                    }
+                }
+
+                assert_eq!(list.len(), model.len());
+                assert!(list.iter().eq(model.iter().map(entry)));
+                assert!(list.iter().rev().eq(model.iter().rev().map(entry)));
+                for (i, (key, value)) in model.values().enumerate() {
+                    assert_eq!(list.get_by_index(i), Some((key, value)));
+                    assert_eq!(list.rank(key), i);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn descending_comparator_matches_btree_map() {
+        matches_ordered_model(Descending, Reverse);
+    }
+
+    #[test]
+    fn closure_comparator_matches_btree_map() {
+        let by_remainder = |a: &u8, b: &u8| (a % 8, a).cmp(&(b % 8, b));
+        matches_ordered_model(by_remainder, |key| (key % 8, key));
+    }
+
+    /// Entries with the even keys below `KEYS`, leaving gaps for bounds
+    /// to fall into.
+    fn evens(seed: u64) -> (List, Model) {
+        let model: Model = (0..KEYS)
+            .step_by(2)
+            .map(|key| (key, key.to_string()))
+            .collect();
+        let mut list = List::with_seed(0.5, 8, seed);
+        list.extend(model.clone());
+        (list, model)
+    }
+
+    #[test]
+    fn bounds_and_ranges_match_btree_map() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        for seed in 0..MODEL_STEPS as u64 / 20 {
+            let (mut list, mut model) = evens(seed);
+            let key = rng.gen_range(0..KEYS);
+            let above = (Bound::Excluded(key), Bound::Unbounded);
+            assert!(list.lower_bound(&key).eq(model.range(key..)));
+            assert!(list.lower_bound(&key).rev().eq(model.range(key..).rev()));
+            assert!(list.upper_bound(&key).eq(model.range(above)));
+            assert!(list.upper_bound(&key).rev().eq(model.range(above).rev()));
 
-                    drop(node)
+            let range = random_range(&mut rng);
+            let mut values = list.range_mut(range);
+            // Taking entries from both ends must not hand one out twice
+            while let Some((_, value)) = values.next() {
+                value.push('<');
+                if let Some((_, value)) = values.next_back() {
+                    value.push('>');
                 }
             }
+            let mut values = model.range_mut(range);
+            while let Some((_, value)) = values.next() {
+                value.push('<');
+                if let Some((_, value)) = values.next_back() {
+                    value.push('>');
+                }
+            }
+            check(&list, &model);
+
+            let range = random_range(&mut rng);
+            let removed = model.range(range).count();
+            model.retain(|key, _| !range.contains(key));
+            assert_eq!(list.remove_range(range), removed);
+            check(&list, &model);
+        }
+    }
+
+    #[test]
+    fn set_operations_match_btree_map() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let mut random = |tag: &str| {
+            let model: Model = (0..rng.gen_range(0..24))
+                .map(|_| rng.gen_range(0..KEYS / 2))
+                .map(|key| (key, format!("{tag}{key}")))
+                .collect();
+            let mut list = List::with_seed(0.5, 8, rng.gen());
+            list.extend(model.clone());
+            (list, model)
+        };
+
+        for _ in 0..MODEL_SEEDS {
+            let (left, left_model) = random("l");
+            let (right, right_model) = random("r");
+            let only = |model: &Model, other: &Model| {
+                let keys: Vec<_> = model
+                    .keys()
+                    .filter(|key| !other.contains_key(key))
+                    .collect();
+                keys.into_iter()
+                    .map(|key| (*key, model[key].clone()))
+                    .collect::<Model>()
+            };
+
+            // Chained last, the left values win
+            let union: Model = right_model
+                .clone()
+                .into_iter()
+                .chain(left_model.clone())
+                .collect();
+            assert!(left.union(&right).eq(union.iter()));
+            let intersection = left_model
+                .iter()
+                .filter(|(key, _)| right_model.contains_key(key));
+            assert!(left.intersection(&right).eq(intersection));
+            let mut symmetric = only(&left_model, &right_model);
+            symmetric.extend(only(&right_model, &left_model));
+            assert!(left.symmetric_difference(&right).eq(symmetric.iter()));
+        }
+    }
+
+    #[test]
+    fn cursor_seeks_and_moves_back() {
+        let mut rng = Xoshiro256Plus::seed_from_u64(0);
+        let (list, model) = evens(0);
+        let mut cursor = list.cursor_front();
+        assert_eq!((cursor.index(), cursor.key()), (0, Some(&0)));
+
+        for _ in 0..MODEL_STEPS / 10 {
+            // Past the last key as well
+            let key = rng.gen_range(0..KEYS + 4);
+            let pos = model.range(..key).count();
+            assert_eq!(cursor.seek(&key), model.contains_key(&key));
+            assert_eq!(cursor.index(), pos);
+            assert_eq!(cursor.entry(), model.iter().nth(pos));
 
-            drop(Box::from_raw(self.head.as_ptr()))
+            let before = cursor.clone();
+            cursor.move_prev();
+            let pos = pos.saturating_sub(1);
+            assert_eq!(cursor.index(), pos);
+            assert_eq!(cursor.entry(), model.iter().nth(pos));
+            assert_eq!(before.index(), model.range(..key).count());
         }
     }
+
+    #[test]
+    fn towers_depend_only_on_the_seed() {
+        let layout = |mut list: List| {
+            list.extend((0..KEYS).map(|key| (key, key.to_string())));
+            let mut dot = Vec::new();
+            list.write_dot(&mut dot).unwrap();
+            String::from_utf8(dot).unwrap()
+        };
+        let seeded = |seed| layout(List::with_seed(0.5, 8, seed));
+
+        assert_eq!(seeded(5), seeded(5));
+        assert_ne!(seeded(5), seeded(6));
+
+        let mut rng = Xoshiro256Plus::seed_from_u64(9);
+        let mut same = rng.clone();
+        assert_eq!(
+            layout(List::with_rng(0.5, 8, &mut rng)),
+            layout(List::with_rng(0.5, 8, &mut same))
+        );
+
+        let mut list = List::with_seed(0.5, 8, 1);
+        list.reseed(5);
+        assert_eq!(layout(list), seeded(5));
+    }
+
+    /// Eight entries whose towers `rebalance` lays out as 0 1 0 2 0 1 0 3.
+    fn rebalanced() -> List {
+        let mut list = List::with_seed(0.5, 8, 0);
+        list.extend((1..=8).map(|key| (key, key.to_string())));
+        list.rebalance();
+        list
+    }
+
+    #[test]
+    fn stats_levels_match_tower_heights() {
+        let mut list = List::with_seed(0.5, 8, 3);
+        list.extend((0..KEYS).map(|key| (key, key.to_string())));
+        let mut levels = vec![0; list.cur_level() + 1];
+        for node in list.node_iter() {
+            levels[unsafe { list.nodes.level(node) }] += 1;
+        }
+
+        let stats = list.stats();
+        assert_eq!(stats.levels, levels);
+        assert_eq!(stats.levels.iter().sum::<usize>(), list.len());
+        assert_eq!((stats.len, stats.cur_level), (list.len(), list.cur_level()));
+    }
+
+    #[test]
+    fn stats_of_rebalanced_list() {
+        let stats = rebalanced().stats();
+        assert_eq!(stats.levels, [4, 2, 1, 1]);
+        // Every level links the head and all its nodes but the last one
+        assert_eq!(stats.pointers, 8 + 4 + 2 + 1);
+        assert_eq!(stats.pointers, rebalanced().node_ptrs());
+        // Keys 1 to 8 take 4 5 5 6 5 6 6 7 steps from the head
+        assert_eq!(stats.avg_search_path, 44.0 / 8.0);
+    }
+
+    #[test]
+    fn stats_of_empty_list() {
+        let stats = List::with_seed(0.5, 8, 0).stats();
+        assert_eq!((stats.len, stats.cur_level, stats.max_level), (0, 0, 8));
+        assert_eq!(stats.levels, [0]);
+        assert_eq!(stats.pointers, 0);
+        assert_eq!(stats.avg_search_path, 0.0);
+        assert!(stats.memory >= size_of::<List>());
+    }
+
+    #[test]
+    fn write_dot_escapes_record_fields() {
+        let mut list = SkipList::with_seed(0.5, 4, 0);
+        list.extend([(String::from("<x|y"), ()), (String::from(r#"{"z"}"#), ())]);
+        list.rebalance();
+
+        let mut dot = Vec::new();
+        list.write_dot(&mut dot).unwrap();
+        let expected = r#"digraph SkipList {
+    rankdir=LR;
+    node [shape=record];
+    head [label="<l1> |<l0> head"];
+    nil [label="<l1> |<l0> nil"];
+    n0 [label="<l0> \"\<x\|y\""];
+    n1 [label="<l1> |<l0> \"\{\\\"z\\\"\}\""];
+    head:l0 -> n0:l0 [label=1];
+    head:l1 -> n1:l1 [label=2];
+    n0:l0 -> n1:l0 [label=1];
+    n1:l0 -> nil:l0 [label=0];
+    n1:l1 -> nil:l1 [label=0];
+}
+"#;
+        assert_eq!(String::from_utf8(dot).unwrap(), expected);
+    }
+
+    #[test]
+    fn unordered_batch_leaves_list_unchanged() {
+        // Goes wrong after 20 keys, once the list had to raise its levels
+        let batch = || (0..20).chain(10..30).map(|key| (key, key.to_string()));
+
+        let mut list = List::with_capacity(4, 0.5);
+        let max_level = list.max_level();
+        assert_eq!(list.bulk_insert(batch()), Err(SkipListError::UnorderedKey));
+        check(&list, &Model::new());
+        assert_eq!(list.capacity(), Some(4));
+        assert_eq!(list.max_level(), max_level);
+
+        let model: Model = (0..KEYS)
+            .step_by(3)
+            .map(|key| (key, key.to_string()))
+            .collect();
+        list.extend(model.clone());
+        let stats = list.stats();
+        assert_eq!(list.bulk_insert(batch()), Err(SkipListError::UnorderedKey));
+        check(&list, &model);
+        assert_eq!(list.stats(), stats);
+    }
+
+    #[test]
+    #[should_panic(expected = "fraction must be between 0 and 1")]
+    fn fraction_of_one_is_rejected() {
+        List::with_capacity(10, 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "fraction must be between 0 and 1")]
+    fn nan_fraction_is_rejected() {
+        List::with_fraction(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "max_level must be at most")]
+    fn huge_max_level_is_rejected() {
+        List::new(0.5, usize::MAX);
+    }
+
+    #[test]
+    fn default_lists_grow_their_levels() {
+        assert_eq!(<List>::default().max_level(), 0);
+
+        let list: List = (0..KEYS).map(|key| (key, key.to_string())).collect();
+        assert_eq!(list.max_level(), 6);
+        let list: List = List::from_sorted_iter((0..KEYS).map(|key| (key, key.to_string())));
+        assert!(list.max_level() <= 7);
+    }
 }
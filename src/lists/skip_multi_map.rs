@@ -0,0 +1,358 @@
+use crate::lists::skip_list::{self, SkipList};
+use rand::RngCore;
+use std::collections::vec_deque::{self, VecDeque};
+use std::fmt::{Debug, Formatter};
+
+/// Ordered map built on a `SkipList` that accepts duplicate keys.
+///
+/// Every key owns a queue of its values, so values under the same key
+/// stay in insertion order and the oldest one is removed first.
+pub struct SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    list: SkipList<K, VecDeque<V>>,
+    len: usize,
+}
+
+pub struct Iter<'a, K: 'a, V: 'a> {
+    keys: skip_list::Iter<'a, K, VecDeque<V>>,
+    current: Option<(&'a K, vec_deque::Iter<'a, V>)>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, values)) = &mut self.current {
+                if let Some(value) = values.next() {
+                    self.len -= 1;
+                    return Some((key, value));
+                }
+            }
+
+            let (key, values) = self.keys.next()?;
+            self.current = Some((key, values.iter()));
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+pub struct GetAll<'a, V: 'a> {
+    inner: vec_deque::Iter<'a, V>,
+}
+
+impl<'a, V> Iterator for GetAll<'a, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, V> DoubleEndedIterator for GetAll<'a, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<K, V> SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    pub fn new(fraction: f64, max_level: usize) -> Self {
+        SkipMultiMap {
+            list: SkipList::new(fraction, max_level),
+            len: 0,
+        }
+    }
+
+    pub fn with_fraction(fraction: f64) -> Self {
+        SkipMultiMap {
+            list: SkipList::with_fraction(fraction),
+            len: 0,
+        }
+    }
+
+    /// See `SkipList::with_capacity`, which counts distinct keys here.
+    pub fn with_capacity(capacity: usize, fraction: f64) -> Self {
+        SkipMultiMap {
+            list: SkipList::with_capacity(capacity, fraction),
+            len: 0,
+        }
+    }
+
+    pub fn with_seed(fraction: f64, max_level: usize, seed: u64) -> Self {
+        SkipMultiMap {
+            list: SkipList::with_seed(fraction, max_level, seed),
+            len: 0,
+        }
+    }
+
+    pub fn with_rng<R: RngCore>(fraction: f64, max_level: usize, rng: &mut R) -> Self {
+        SkipMultiMap {
+            list: SkipList::with_rng(fraction, max_level, rng),
+            len: 0,
+        }
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.list.reseed(seed)
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+        self.len = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Counts every stored value, duplicates included.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Counts distinct keys.
+    pub fn keys_len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn cur_level(&self) -> usize {
+        self.list.cur_level()
+    }
+
+    pub fn max_level(&self) -> usize {
+        self.list.max_level()
+    }
+
+    /// Adds `value` after the values already stored under `key`.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.list.entry(key).or_default().push_back(value);
+        self.len += 1;
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.list.find(key).is_some()
+    }
+
+    /// Returns the oldest value stored under `key`.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.list.find(key)?.front()
+    }
+
+    /// Iterates over the values under `key` in insertion order.
+    pub fn get_all(&self, key: &K) -> GetAll<'_, V> {
+        GetAll {
+            inner: self
+                .list
+                .find(key)
+                .map(|values| values.iter())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn count(&self, key: &K) -> usize {
+        self.list.find(key).map_or(0, |values| values.len())
+    }
+
+    /// Removes the oldest value stored under `key`.
+    pub fn remove_one(&mut self, key: &K) -> Option<V> {
+        let values = self.list.find_mut(key)?;
+        let value = values.pop_front();
+        if values.is_empty() {
+            self.list.pop(key);
+        }
+
+        self.len -= 1;
+        value
+    }
+
+    /// Removes every value under `key`, returning them in insertion order.
+    pub fn remove_all(&mut self, key: &K) -> Vec<V> {
+        let values = self.list.pop(key).unwrap_or_default();
+        self.len -= values.len();
+        values.into()
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let (key, values) = self.list.first()?;
+        Some((key, values.front()?))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let (key, values) = self.list.last()?;
+        Some((key, values.back()?))
+    }
+
+    /// Iterates over all pairs in key order, duplicates in insertion order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            keys: self.list.iter(),
+            current: None,
+            len: self.len,
+        }
+    }
+
+    /// Iterates over the distinct keys.
+    pub fn keys(&self) -> skip_list::Keys<'_, K, VecDeque<V>> {
+        self.list.keys()
+    }
+}
+
+impl<K, V> Default for SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        SkipMultiMap {
+            list: SkipList::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<K, V> Clone for SkipMultiMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        SkipMultiMap {
+            list: self.list.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V> Extend<(K, V)> for SkipMultiMap<K, V>
+where
+    K: Ord,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V> Debug for SkipMultiMap<K, V>
+where
+    K: Ord + Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SkipMultiMap;
+    use crate::test_support::{Seeded, MODEL_SEEDS, MODEL_STEPS};
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use std::collections::BTreeMap;
+
+    type Map = SkipMultiMap<u8, String>;
+    type Model = BTreeMap<u8, Vec<String>>;
+
+    /// Fewer keys than values, so most keys hold several of them.
+    const KEYS: u8 = 16;
+
+    fn check(map: &Map, model: &Model) {
+        let pairs = model
+            .iter()
+            .flat_map(|(key, values)| values.iter().map(move |value| (key, value)));
+        assert_eq!(map.len(), pairs.clone().count());
+        assert_eq!(map.keys_len(), model.len());
+        assert!(map.iter().eq(pairs));
+        assert!(map.keys().eq(model.keys()));
+
+        let first = model.first_key_value();
+        let last = model.last_key_value();
+        assert_eq!(map.first(), first.map(|(key, values)| (key, &values[0])));
+        assert_eq!(
+            map.last(),
+            last.and_then(|(key, values)| Some((key, values.last()?)))
+        );
+    }
+
+    #[test]
+    fn matches_btree_map_of_vecs() {
+        for seed in 0..MODEL_SEEDS {
+            let mut rng = Xoshiro256Plus::seed_from_u64(seed);
+            let mut map = Map::for_seed(seed);
+            let mut model = Model::new();
+
+            for step in 0..MODEL_STEPS {
+                let key = rng.gen_range(0..KEYS);
+                match rng.gen_range(0..8) {
+                    0..=3 => {
+                        map.insert(key, step.to_string());
+                        model.entry(key).or_default().push(step.to_string());
+                    }
+                    4 => {
+                        let values = model.get_mut(&key);
+                        let expected = values.map(|values| values.remove(0));
+                        model.retain(|_, values| !values.is_empty());
+                        assert_eq!(map.remove_one(&key), expected);
+                    }
+                    5 => assert_eq!(map.remove_all(&key), model.remove(&key).unwrap_or_default()),
+                    6 => {
+                        let values = model.get(&key);
+                        assert_eq!(map.contains_key(&key), values.is_some());
+                        assert_eq!(map.get(&key), values.map(|values| &values[0]));
+                        assert_eq!(map.count(&key), values.map_or(0, Vec::len));
+                        assert!(map.get_all(&key).eq(values.into_iter().flatten()));
+                        assert!(map
+                            .get_all(&key)
+                            .rev()
+                            .eq(values.into_iter().flatten().rev()));
+                    }
+                    _ => match rng.gen() {
+                        true => map = map.clone(),
+                        false => {
+                            map.clear();
+                            model.clear();
+                        }
+                    },
+                }
+                check(&map, &model);
+            }
+        }
+    }
+}
@@ -0,0 +1,585 @@
+use crate::error::SkipListError;
+use crate::lists::skip_list::{self, SkipList};
+use rand::RngCore;
+use std::fmt::{Debug, Formatter};
+use std::ops::RangeBounds;
+
+/// Ordered set built on a `SkipList` with unit values.
+pub struct SkipSet<K>
+where
+    K: Ord,
+{
+    list: SkipList<K, ()>,
+}
+
+pub struct Iter<'a, K: 'a> {
+    inner: skip_list::Keys<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for Iter<'a, K> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+pub struct IntoIter<K: Ord> {
+    inner: skip_list::IntoIter<K, ()>,
+}
+
+impl<K> Iterator for IntoIter<K>
+where
+    K: Ord,
+{
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K> DoubleEndedIterator for IntoIter<K>
+where
+    K: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+pub struct Range<'a, K: Ord + 'a> {
+    inner: skip_list::Range<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Range<'a, K>
+where
+    K: Ord,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for Range<'a, K>
+where
+    K: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+pub struct Union<'a, K: 'a> {
+    inner: skip_list::Union<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Union<'a, K>
+where
+    K: Ord,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct Intersection<'a, K: 'a> {
+    inner: skip_list::Intersection<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Intersection<'a, K>
+where
+    K: Ord,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct Difference<'a, K: 'a> {
+    inner: skip_list::Difference<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Difference<'a, K>
+where
+    K: Ord,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub struct SymmetricDifference<'a, K: 'a> {
+    inner: skip_list::SymmetricDifference<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for SymmetricDifference<'a, K>
+where
+    K: Ord,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K> SkipSet<K>
+where
+    K: Ord,
+{
+    pub fn new(fraction: f64, max_level: usize) -> Self {
+        SkipSet {
+            list: SkipList::new(fraction, max_level),
+        }
+    }
+
+    pub fn with_fraction(fraction: f64) -> Self {
+        SkipSet {
+            list: SkipList::with_fraction(fraction),
+        }
+    }
+
+    /// See `SkipList::with_capacity`.
+    pub fn with_capacity(capacity: usize, fraction: f64) -> Self {
+        SkipSet {
+            list: SkipList::with_capacity(capacity, fraction),
+        }
+    }
+
+    pub fn with_seed(fraction: f64, max_level: usize, seed: u64) -> Self {
+        SkipSet {
+            list: SkipList::with_seed(fraction, max_level, seed),
+        }
+    }
+
+    pub fn with_rng<R: RngCore>(fraction: f64, max_level: usize, rng: &mut R) -> Self {
+        SkipSet {
+            list: SkipList::with_rng(fraction, max_level, rng),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn raise_max_level(&mut self, max_level: usize) {
+        self.list.raise_max_level(max_level)
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.list.reseed(seed)
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn cur_level(&self) -> usize {
+        self.list.cur_level()
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.list.fraction()
+    }
+
+    pub fn max_level(&self) -> usize {
+        self.list.max_level()
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.list.capacity()
+    }
+
+    /// Returns `false` if `key` was already in the set.
+    pub fn insert(&mut self, key: K) -> bool {
+        self.list.insert(key, ()).is_ok()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.list.find(key).is_some()
+    }
+
+    /// Returns `false` if `key` was not in the set.
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.list.pop(key).is_some()
+    }
+
+    pub fn get_by_index(&self, index: usize) -> Option<&K> {
+        self.list.get_by_index(index).map(|(key, _)| key)
+    }
+
+    /// Returns how many keys are strictly less than `key`.
+    pub fn rank(&self, key: &K) -> usize {
+        self.list.rank(key)
+    }
+
+    pub fn first(&self) -> Option<&K> {
+        self.list.first().map(|(key, _)| key)
+    }
+
+    pub fn last(&self) -> Option<&K> {
+        self.list.last().map(|(key, _)| key)
+    }
+
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.list.pop_first().map(|(key, _)| key)
+    }
+
+    pub fn pop_last(&mut self) -> Option<K> {
+        self.list.pop_last().map(|(key, _)| key)
+    }
+
+    /// Returns the greatest key less than or equal to `key`.
+    pub fn floor(&self, key: &K) -> Option<&K> {
+        self.list.floor(key).map(|(key, _)| key)
+    }
+
+    /// Returns the least key greater than or equal to `key`.
+    pub fn ceiling(&self, key: &K) -> Option<&K> {
+        self.list.ceiling(key).map(|(key, _)| key)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter {
+            inner: self.list.keys(),
+        }
+    }
+
+    pub fn range<R>(&self, range: R) -> Range<'_, K>
+    where
+        R: RangeBounds<K>,
+    {
+        Range {
+            inner: self.list.range(range),
+        }
+    }
+
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, K> {
+        Union {
+            inner: self.list.union(&other.list),
+        }
+    }
+
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, K> {
+        Intersection {
+            inner: self.list.intersection(&other.list),
+        }
+    }
+
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, K> {
+        Difference {
+            inner: self.list.difference(&other.list),
+        }
+    }
+
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, K> {
+        SymmetricDifference {
+            inner: self.list.symmetric_difference(&other.list),
+        }
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.len() <= other.len() && self.difference(other).next().is_none()
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    /// Moves every key of `other` into this set, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.list.append(&mut other.list)
+    }
+
+    /// Moves the keys not less than `key` into the returned set.
+    pub fn split_off(&mut self, key: &K) -> Self {
+        SkipSet {
+            list: self.list.split_off(key),
+        }
+    }
+
+    /// Builds a set from keys in ascending order in O(n).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not in ascending order.
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+    {
+        SkipSet {
+            list: SkipList::from_sorted_iter(iter.into_iter().map(|key| (key, ()))),
+        }
+    }
+
+    /// Same as `from_sorted_iter`, for a set with the given `fraction`
+    /// and `max_level`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the keys are not in ascending order.
+    pub fn from_sorted_iter_with<I>(fraction: f64, max_level: usize, iter: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+    {
+        SkipSet {
+            list: SkipList::from_sorted_iter_with(
+                fraction,
+                max_level,
+                iter.into_iter().map(|key| (key, ())),
+            ),
+        }
+    }
+
+    /// Merges a batch of keys in ascending order in a single forward pass.
+    ///
+    /// Returns `UnorderedKey` and leaves the set unchanged if the keys
+    /// are not in ascending order.
+    pub fn bulk_insert<I>(&mut self, iter: I) -> Result<(), SkipListError>
+    where
+        I: IntoIterator<Item = K>,
+    {
+        self.list.bulk_insert(iter.into_iter().map(|key| (key, ())))
+    }
+
+    pub fn rebalance(&mut self) {
+        self.list.rebalance()
+    }
+
+    pub fn node_ptrs(&self) -> usize {
+        self.list.node_ptrs()
+    }
+}
+
+impl<K> Default for SkipSet<K>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        SkipSet {
+            list: SkipList::default(),
+        }
+    }
+}
+
+impl<K> Clone for SkipSet<K>
+where
+    K: Ord + Clone,
+{
+    fn clone(&self) -> Self {
+        SkipSet {
+            list: self.list.clone(),
+        }
+    }
+}
+
+impl<K> IntoIterator for SkipSet<K>
+where
+    K: Ord,
+{
+    type Item = K;
+    type IntoIter = IntoIter<K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.list.into_iter(),
+        }
+    }
+}
+
+impl<'a, K> IntoIterator for &'a SkipSet<K>
+where
+    K: Ord,
+{
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K> FromIterator<K> for SkipSet<K>
+where
+    K: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = Self::default();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<K> Extend<K> for SkipSet<K>
+where
+    K: Ord,
+{
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K> Debug for SkipSet<K>
+where
+    K: Ord + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SkipSet;
+    use crate::error::SkipListError;
+    use crate::test_support::{Seeded, KEYS, MODEL_SEEDS, MODEL_STEPS};
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+    use std::collections::BTreeSet;
+
+    type Set = SkipSet<u8>;
+    type Model = BTreeSet<u8>;
+
+    fn check(set: &Set, model: &Model) {
+        assert_eq!(set.len(), model.len());
+        assert!(set.iter().eq(model.iter()));
+        assert!(set.iter().rev().eq(model.iter().rev()));
+        assert_eq!(set.first(), model.first());
+        assert_eq!(set.last(), model.last());
+
+        for (i, key) in model.iter().enumerate() {
+            assert_eq!(set.get_by_index(i), Some(key));
+            assert_eq!(set.rank(key), i);
+        }
+    }
+
+    fn random_set(rng: &mut Xoshiro256Plus, seed: u64) -> (Set, Model) {
+        let model: Model = (0..rng.gen_range(0..16))
+            .map(|_| rng.gen_range(0..KEYS))
+            .collect();
+        let mut set = Set::with_seed(0.5, 8, seed);
+        set.extend(model.iter().copied());
+        (set, model)
+    }
+
+    #[test]
+    fn matches_btree_set() {
+        for seed in 0..MODEL_SEEDS {
+            let mut rng = Xoshiro256Plus::seed_from_u64(seed);
+            let mut set = Set::for_seed(seed);
+            let mut model = Model::new();
+
+            for step in 0..MODEL_STEPS {
+                let key = rng.gen_range(0..KEYS);
+                match rng.gen_range(0..10) {
+                    0..=2 => assert_eq!(set.insert(key), model.insert(key)),
+                    3..=4 => assert_eq!(set.remove(&key), model.remove(&key)),
+                    5 => match rng.gen() {
+                        true => assert_eq!(set.pop_first(), model.pop_first()),
+                        false => assert_eq!(set.pop_last(), model.pop_last()),
+                    },
+                    6 => {
+                        assert_eq!(set.contains(&key), model.contains(&key));
+                        assert_eq!(set.floor(&key), model.range(..=key).next_back());
+                        assert_eq!(set.ceiling(&key), model.range(key..).next());
+                        assert!(set.range(key..).eq(model.range(key..)));
+                        assert!(set.range(..key).rev().eq(model.range(..key).rev()));
+                    }
+                    7 => {
+                        let (other, other_model) = random_set(&mut rng, step as u64);
+                        assert!(set.union(&other).eq(model.union(&other_model)));
+                        assert!(set
+                            .intersection(&other)
+                            .eq(model.intersection(&other_model)));
+                        assert!(set.difference(&other).eq(model.difference(&other_model)));
+                        assert!(set
+                            .symmetric_difference(&other)
+                            .eq(model.symmetric_difference(&other_model)));
+                        assert_eq!(set.is_subset(&other), model.is_subset(&other_model));
+                        assert_eq!(set.is_superset(&other), model.is_superset(&other_model));
+                        assert_eq!(set.is_disjoint(&other), model.is_disjoint(&other_model));
+                    }
+                    8 => {
+                        let mut other = set.split_off(&key);
+                        let mut other_model = model.split_off(&key);
+                        check(&set, &model);
+                        check(&other, &other_model);
+                        set.append(&mut other);
+                        model.append(&mut other_model);
+                    }
+                    _ => {
+                        let keys: Vec<_> =
+                            random_set(&mut rng, step as u64).1.into_iter().collect();
+                        if keys.len() > 1 && rng.gen_ratio(1, 4) {
+                            let reversed = keys.into_iter().rev();
+                            assert_eq!(set.bulk_insert(reversed), Err(SkipListError::UnorderedKey));
+                        } else {
+                            assert_eq!(set.bulk_insert(keys.iter().copied()), Ok(()));
+                            model.extend(keys);
+                        }
+                    }
+                }
+                check(&set, &model);
+            }
+
+            let taken = rng.gen_range(0..=model.len());
+            let rebuilt = Set::from_sorted_iter(model.iter().copied());
+            check(&rebuilt, &model);
+            assert!(set
+                .into_iter()
+                .rev()
+                .take(taken)
+                .eq(model.into_iter().rev().take(taken)));
+        }
+    }
+}
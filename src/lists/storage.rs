@@ -0,0 +1,273 @@
+use crate::MaybeNone;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+pub(crate) use raw::{NodePtr, Nodes};
+
+/// Where a `SkipList` keeps its nodes.
+///
+/// The list holds the head and walks the towers through node handles, so
+/// the search, link and span code is the same for every storage. The trait
+/// is sealed: `Boxed`, `Arena` and `Shared` are the only implementations.
+pub trait Storage<K, V>: Nodes<K, V> {}
+
+impl<K, V, S: Nodes<K, V>> Storage<K, V> for S {}
+
+mod raw {
+    use std::hash::Hash;
+    use std::ptr::NonNull;
+
+    /// Handles are only meaningful to the storage that handed them out, and
+    /// only until the node is freed. Every `unsafe` method expects a handle
+    /// to a live node of `self` and a level within that node's tower.
+    pub trait Nodes<K, V>: Default {
+        type Id: Copy + Eq + Hash;
+
+        /// Whether the handles of one storage stay valid in every other, so
+        /// that whole towers can be relinked from one list into another.
+        const GLOBAL_IDS: bool;
+
+        /// Adds an unlinked node with a tower of `level + 1` links.
+        fn alloc(&mut self, key: K, value: V, level: usize) -> Self::Id;
+
+        /// Takes the entry out of an unlinked node and frees the node.
+        unsafe fn free(&mut self, node: Self::Id) -> (K, V);
+
+        /// Moves an unlinked node of `from` over, keeping its height.
+        /// Its links are left for the caller to set.
+        unsafe fn adopt(&mut self, from: &mut Self, node: Self::Id) -> Self::Id;
+
+        /// Frees the nodes chained on level 0 from `first`, along with
+        /// anything else the storage still holds.
+        unsafe fn clear(&mut self, first: Option<Self::Id>);
+
+        /// Copies every node under the same handle, if the storage can.
+        fn try_clone(&self) -> Option<Self>
+        where
+            K: Clone,
+            V: Clone;
+
+        /// Returns the top level of the node's tower.
+        unsafe fn level(&self, node: Self::Id) -> usize;
+
+        /// Resizes the tower of an unlinked node.
+        unsafe fn set_level(&mut self, node: Self::Id, level: usize);
+
+        unsafe fn next(&self, node: Self::Id, lvl: usize) -> Option<Self::Id>;
+
+        /// Sets the forward link alone, the back link is up to the caller.
+        unsafe fn set_next(&mut self, node: Self::Id, lvl: usize, to: Option<Self::Id>);
+
+        /// Returns how many level 0 steps the `lvl` link of `node` skips.
+        unsafe fn span(&self, node: Self::Id, lvl: usize) -> usize;
+
+        unsafe fn span_mut(&mut self, node: Self::Id, lvl: usize) -> &mut usize;
+
+        /// Returns the previous node on level 0, `None` for the first one.
+        unsafe fn prev(&self, node: Self::Id) -> Option<Self::Id>;
+
+        unsafe fn set_prev(&mut self, node: Self::Id, to: Option<Self::Id>);
+
+        unsafe fn key(&self, node: Self::Id) -> &K;
+
+        unsafe fn value(&self, node: Self::Id) -> &V;
+
+        /// Borrows the entry of `node` without touching any other node, so
+        /// that iterators may hand out the values of several nodes at once.
+        unsafe fn entry_mut(&mut self, node: Self::Id) -> (&K, &mut V);
+
+        unsafe fn value_mut(&mut self, node: Self::Id) -> &mut V;
+
+        /// Returns the bytes held by the storage besides its nodes.
+        fn memory(&self) -> usize;
+
+        /// Returns the bytes taken by one node.
+        unsafe fn node_memory(&self, node: Self::Id) -> usize;
+    }
+
+    /// Address of a `Boxed` node. It carries no access rights of its own:
+    /// nodes are only reached through the storage, whose borrow decides
+    /// what may happen to them.
+    pub struct NodePtr<K, V>(pub(super) NonNull<super::SkipNode<K, V>>);
+}
+
+/// Default storage: every node is a separate heap allocation, and a node
+/// handle is its address. Handles stay valid when a node moves to another
+/// list, so `append` and `split_off` relink whole towers in O(log n).
+pub struct Boxed<K, V> {
+    marker: PhantomData<(K, V)>,
+}
+
+struct SkipNode<K, V> {
+    next: Vec<MaybeNone<SkipNode<K, V>>>,
+    /// Previous node on level 0, `None` for the first one.
+    prev: MaybeNone<SkipNode<K, V>>,
+    span: Vec<usize>,
+    key: K,
+    value: V,
+}
+
+impl<K, V> SkipNode<K, V> {
+    fn new(key: K, value: V, level: usize) -> Self {
+        SkipNode {
+            next: vec![None; level + 1],
+            prev: None,
+            span: vec![0; level + 1],
+            key,
+            value,
+        }
+    }
+}
+
+impl<K, V> Clone for NodePtr<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for NodePtr<K, V> {}
+
+impl<K, V> PartialEq for NodePtr<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K, V> Eq for NodePtr<K, V> {}
+
+impl<K, V> Hash for NodePtr<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+// A handle is only dereferenced through the `Boxed` storage it came from,
+// so the storage's own auto traits are what counts.
+unsafe impl<K, V> Send for NodePtr<K, V> {}
+unsafe impl<K, V> Sync for NodePtr<K, V> {}
+
+impl<K, V> NodePtr<K, V> {
+    /// The lifetime is up to the caller, who holds the matching borrow of
+    /// the storage.
+    unsafe fn get<'a>(self) -> &'a SkipNode<K, V> {
+        &*self.0.as_ptr()
+    }
+
+    unsafe fn get_mut<'a>(self) -> &'a mut SkipNode<K, V> {
+        &mut *self.0.as_ptr()
+    }
+}
+
+fn node_ptr<K, V>(next: MaybeNone<SkipNode<K, V>>) -> Option<NodePtr<K, V>> {
+    next.map(NodePtr)
+}
+
+impl<K, V> Boxed<K, V> {
+    pub fn new() -> Self {
+        Boxed {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Default for Boxed<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Nodes<K, V> for Boxed<K, V> {
+    type Id = NodePtr<K, V>;
+
+    const GLOBAL_IDS: bool = true;
+
+    fn alloc(&mut self, key: K, value: V, level: usize) -> Self::Id {
+        let node = SkipNode::new(key, value, level);
+        NodePtr(NonNull::from(Box::leak(Box::new(node))))
+    }
+
+    unsafe fn free(&mut self, node: Self::Id) -> (K, V) {
+        let node = Box::from_raw(node.0.as_ptr());
+        (node.key, node.value)
+    }
+
+    unsafe fn adopt(&mut self, _from: &mut Self, node: Self::Id) -> Self::Id {
+        node
+    }
+
+    unsafe fn clear(&mut self, first: Option<Self::Id>) {
+        let mut node = first.map(|node| node.0);
+        while let Some(ptr) = node {
+            let boxed = Box::from_raw(ptr.as_ptr());
+            node = boxed.next[0];
+        }
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        None
+    }
+
+    unsafe fn level(&self, node: Self::Id) -> usize {
+        node.get().next.len() - 1
+    }
+
+    unsafe fn set_level(&mut self, node: Self::Id, level: usize) {
+        let node = node.get_mut();
+        node.next.resize(level + 1, None);
+        node.span.resize(level + 1, 0);
+    }
+
+    unsafe fn next(&self, node: Self::Id, lvl: usize) -> Option<Self::Id> {
+        node_ptr(node.get().next[lvl])
+    }
+
+    unsafe fn set_next(&mut self, node: Self::Id, lvl: usize, to: Option<Self::Id>) {
+        node.get_mut().next[lvl] = to.map(|to| to.0);
+    }
+
+    unsafe fn span(&self, node: Self::Id, lvl: usize) -> usize {
+        node.get().span[lvl]
+    }
+
+    unsafe fn span_mut(&mut self, node: Self::Id, lvl: usize) -> &mut usize {
+        &mut node.get_mut().span[lvl]
+    }
+
+    unsafe fn prev(&self, node: Self::Id) -> Option<Self::Id> {
+        node_ptr(node.get().prev)
+    }
+
+    unsafe fn set_prev(&mut self, node: Self::Id, to: Option<Self::Id>) {
+        node.get_mut().prev = to.map(|to| to.0);
+    }
+
+    unsafe fn key(&self, node: Self::Id) -> &K {
+        &node.get().key
+    }
+
+    unsafe fn value(&self, node: Self::Id) -> &V {
+        &node.get().value
+    }
+
+    unsafe fn entry_mut(&mut self, node: Self::Id) -> (&K, &mut V) {
+        let node = node.get_mut();
+        (&node.key, &mut node.value)
+    }
+
+    unsafe fn value_mut(&mut self, node: Self::Id) -> &mut V {
+        &mut node.get_mut().value
+    }
+
+    fn memory(&self) -> usize {
+        0
+    }
+
+    unsafe fn node_memory(&self, node: Self::Id) -> usize {
+        let node = node.get();
+        size_of::<SkipNode<K, V>>()
+            + node.next.capacity() * size_of::<MaybeNone<SkipNode<K, V>>>()
+            + node.span.capacity() * size_of::<usize>()
+    }
+}
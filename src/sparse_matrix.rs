@@ -2,6 +2,7 @@ use crate::MaybeNone;
 use num::traits::NumAssign;
 use num::Num;
 use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
 use std::ptr::NonNull;
 
 #[derive(Clone)]
@@ -46,7 +47,28 @@ pub struct SparseMatrix<T: NumAssign + Copy> {
     size: usize,
 }
 
-pub struct AxisIter<T>
+/// Iterator over the values of one row or column.
+///
+/// ```
+/// use advanced_structures::sparse_matrix::SparseMatrix;
+///
+/// let matrix = SparseMatrix::from_2d_vec(vec![vec![1, 2], vec![3, 4]]);
+/// let iter = matrix.row_iter(0);
+/// assert_eq!(iter.collect::<Vec<i32>>(), vec![1, 2]);
+/// ```
+///
+/// It borrows the matrix, so it cannot outlive it:
+///
+/// ```compile_fail,E0597
+/// use advanced_structures::sparse_matrix::SparseMatrix;
+///
+/// let iter = {
+///     let matrix = SparseMatrix::from_2d_vec(vec![vec![1, 2], vec![3, 4]]);
+///     matrix.row_iter(0)
+/// };
+/// println!("{:?}", iter.collect::<Vec<i32>>());
+/// ```
+pub struct AxisIter<'a, T: 'a>
 where
     T: Num + Clone,
 {
@@ -54,9 +76,10 @@ where
     axis: usize,
     len: usize,
     max_len: usize,
+    marker: PhantomData<&'a Node<T>>,
 }
 
-impl<T> Iterator for AxisIter<T>
+impl<'a, T> Iterator for AxisIter<'a, T>
 where
     T: Num + Clone,
 {
@@ -112,7 +135,7 @@ where
             return None;
         }
 
-        self.head.map(|nd| unsafe {
+        self.head.inspect(|nd| unsafe {
             let node = nd.as_ref();
             let next = match self.axis {
                 0 => node.next_col,
@@ -122,7 +145,6 @@ where
 
             self.len -= 1;
             self.head = next;
-            nd
         })
     }
 
@@ -208,6 +230,10 @@ where
     }
 
     pub fn mul_by(&self, num: T) -> Self {
+        if num == T::zero() {
+            return Self::new(self.rows(), self.cols());
+        }
+
         let clone = self.clone();
         for row in 0..clone.rows() {
             let iter = clone.node_row_iter(row);
@@ -239,10 +265,11 @@ where
                     self.size -= 1;
                 } else {
                     prev_col.as_ref().next_col.unwrap().as_mut().value = value;
-                    self.size += 1;
                 }
-            } else {
-                let node = Box::new(Node::new(value, row, col));
+            } else if !value_is_zero {
+                let mut node = Box::new(Node::new(value, row, col));
+                node.next_row = prev_row.as_ref().next_row;
+                node.next_col = prev_col.as_ref().next_col;
                 let ptr = Some(Box::leak(node).into());
                 prev_row.as_mut().next_row = ptr;
                 prev_col.as_mut().next_col = ptr;
@@ -289,21 +316,23 @@ where
         }
     }
 
-    pub fn row_iter(&self, row: usize) -> AxisIter<T> {
+    pub fn row_iter(&self, row: usize) -> AxisIter<'_, T> {
         AxisIter {
             head: unsafe { self.rows_vec[row].as_ref().next_row },
             axis: 1,
             len: self.cols(),
             max_len: self.cols(),
+            marker: PhantomData,
         }
     }
 
-    pub fn col_iter(&self, col: usize) -> AxisIter<T> {
+    pub fn col_iter(&self, col: usize) -> AxisIter<'_, T> {
         AxisIter {
             head: unsafe { self.cols_vec[col].as_ref().next_col },
             axis: 0,
             len: self.rows(),
             max_len: self.rows(),
+            marker: PhantomData,
         }
     }
 
@@ -311,7 +340,7 @@ where
         NodeAxisIter {
             head: unsafe { self.rows_vec[row].as_ref().next_row },
             axis: 1,
-            len: self.rows(),
+            len: self.cols(),
         }
     }
 
@@ -415,3 +444,106 @@ where
         }
     }
 }
+
+// The matrix owns all of its nodes and only writes to them through `&mut self`,
+// so it is as thread safe as a `Vec<T>` would be.
+unsafe impl<T> Send for SparseMatrix<T> where T: NumAssign + Copy + Send {}
+
+unsafe impl<T> Sync for SparseMatrix<T> where T: NumAssign + Copy + Sync {}
+
+unsafe impl<T> Send for AxisIter<'_, T> where T: Num + Clone + Sync {}
+
+unsafe impl<T> Sync for AxisIter<'_, T> where T: Num + Clone + Sync {}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseMatrix;
+    use crate::test_support::{MODEL_SEEDS, MODEL_STEPS};
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256Plus;
+
+    type Dense = Vec<Vec<i64>>;
+
+    fn random_dense(rng: &mut Xoshiro256Plus, rows: usize, cols: usize) -> Dense {
+        (0..rows)
+            .map(|_| (0..cols).map(|_| random_value(rng)).collect())
+            .collect()
+    }
+
+    /// Zero half of the time, so that entries keep being added and removed.
+    fn random_value(rng: &mut Xoshiro256Plus) -> i64 {
+        if rng.gen() {
+            0
+        } else {
+            rng.gen_range(-9..=9)
+        }
+    }
+
+    fn check(matrix: &SparseMatrix<i64>, model: &Dense) {
+        let (rows, cols) = (model.len(), model[0].len());
+        assert_eq!((matrix.rows(), matrix.cols()), (rows, cols));
+
+        for (row, values) in model.iter().enumerate() {
+            assert!(matrix.row_iter(row).eq(values.iter().copied()));
+            for (col, value) in values.iter().enumerate() {
+                assert_eq!(matrix.get(row, col), *value);
+            }
+        }
+        for col in 0..cols {
+            assert!(matrix
+                .col_iter(col)
+                .eq(model.iter().map(|values| values[col])));
+        }
+
+        let zeros = model.iter().flatten().filter(|value| **value == 0).count();
+        assert_eq!(matrix.sparsity(), zeros as f64 / (rows * cols) as f64);
+    }
+
+    #[test]
+    fn matches_dense_matrix() {
+        for seed in 0..MODEL_SEEDS {
+            let mut rng = Xoshiro256Plus::seed_from_u64(seed);
+            let (rows, cols) = (rng.gen_range(1..7), rng.gen_range(1..7));
+            let mut matrix = SparseMatrix::new(rows, cols);
+            let mut model = vec![vec![0; cols]; rows];
+
+            for _ in 0..MODEL_STEPS {
+                match rng.gen_range(0..8) {
+                    0..=3 => {
+                        let (row, col) = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+                        let value = random_value(&mut rng);
+                        matrix.set(value, row, col);
+                        model[row][col] = value;
+                    }
+                    4 => {
+                        let other = random_dense(&mut rng, rows, cols);
+                        matrix = matrix.add(&SparseMatrix::from_2d_vec(other.clone()));
+                        for (values, others) in model.iter_mut().zip(other) {
+                            values.iter_mut().zip(others).for_each(|(a, b)| *a += b);
+                        }
+                    }
+                    5 => {
+                        let num = rng.gen_range(-2..=2);
+                        matrix = matrix.mul_by(num);
+                        model.iter_mut().flatten().for_each(|value| *value *= num);
+                    }
+                    6 => {
+                        let transposed = matrix.transposed();
+                        let model_t: Dense = (0..cols)
+                            .map(|col| model.iter().map(|values| values[col]).collect())
+                            .collect();
+                        check(&transposed, &model_t);
+                        check(&transposed.transposed(), &model);
+                    }
+                    _ => {
+                        let clone = matrix.clone();
+                        matrix.set(1, 0, 0);
+                        check(&clone, &model);
+                        matrix = clone;
+                    }
+                }
+                check(&matrix, &model);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Helpers shared by the model tests, which run a structure against its
+//! `std` counterpart through a long random sequence of operations.
+
+use crate::lists::storage::Storage;
+use crate::lists::{Natural, SkipList, SkipMultiMap, SkipSet};
+use rand::Rng;
+use rand_xoshiro::Xoshiro256Plus;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// Number of seeds and operations per seed used by the model tests, cut down
+/// under Miri so that `cargo +nightly miri test` stays within minutes.
+pub const MODEL_SEEDS: u64 = if cfg!(miri) { 2 } else { 16 };
+pub const MODEL_STEPS: usize = if cfg!(miri) { 150 } else { 2_000 };
+
+/// Keys are drawn from a small range so that lookups hit about half
+/// of the time.
+pub const KEYS: u8 = 64;
+
+/// The skip list based structures, built the two ways a model test covers.
+pub trait Seeded: Sized {
+    fn with_seed(fraction: f64, max_level: usize, seed: u64) -> Self;
+
+    /// Builds an empty structure in capacity mode.
+    fn with_capacity(capacity: usize, fraction: f64) -> Self;
+
+    fn reseed(&mut self, seed: u64);
+
+    /// Even seeds get a fixed maximum level, odd ones start in capacity
+    /// mode, so that both ways of sizing the towers are run.
+    fn for_seed(seed: u64) -> Self {
+        match seed % 2 {
+            0 => Self::with_seed(0.5, 8, seed),
+            _ => {
+                let mut list = Self::with_capacity(0, 0.5);
+                list.reseed(seed);
+                list
+            }
+        }
+    }
+}
+
+impl<K: Ord, V, S: Storage<K, V>> Seeded for SkipList<K, V, Natural, S> {
+    fn with_seed(fraction: f64, max_level: usize, seed: u64) -> Self {
+        Self::with_seed_in(fraction, max_level, seed, S::default())
+    }
+
+    fn with_capacity(capacity: usize, fraction: f64) -> Self {
+        Self::with_capacity_in(capacity, fraction, S::default())
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.reseed(seed)
+    }
+}
+
+impl<K: Ord> Seeded for SkipSet<K> {
+    fn with_seed(fraction: f64, max_level: usize, seed: u64) -> Self {
+        Self::with_seed(fraction, max_level, seed)
+    }
+
+    fn with_capacity(capacity: usize, fraction: f64) -> Self {
+        Self::with_capacity(capacity, fraction)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.reseed(seed)
+    }
+}
+
+impl<K: Ord, V> Seeded for SkipMultiMap<K, V> {
+    fn with_seed(fraction: f64, max_level: usize, seed: u64) -> Self {
+        Self::with_seed(fraction, max_level, seed)
+    }
+
+    fn with_capacity(capacity: usize, fraction: f64) -> Self {
+        Self::with_capacity(capacity, fraction)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.reseed(seed)
+    }
+}
+
+/// Compares a list with its model through iteration from both ends,
+/// the first and last entries and every position.
+pub fn check<S: Storage<u8, String>>(
+    list: &SkipList<u8, String, Natural, S>,
+    model: &BTreeMap<u8, String>,
+) {
+    assert_eq!(list.len(), model.len());
+    assert!(list.iter().eq(model.iter()));
+    assert!(list.iter().rev().eq(model.iter().rev()));
+    assert_eq!(list.first(), model.first_key_value());
+    assert_eq!(list.last(), model.last_key_value());
+
+    for (i, (key, value)) in model.iter().enumerate() {
+        assert_eq!(list.get_by_index(i), Some((key, value)));
+        assert_eq!(list.rank(key), i);
+    }
+    assert_eq!(list.get_by_index(model.len()), None);
+}
+
+fn random_bound(rng: &mut Xoshiro256Plus) -> Bound<u8> {
+    let key = rng.gen_range(0..KEYS);
+    match rng.gen_range(0..3) {
+        0 => Bound::Included(key),
+        1 => Bound::Excluded(key),
+        _ => Bound::Unbounded,
+    }
+}
+
+/// Bounds that `BTreeMap::range` accepts.
+pub fn random_range(rng: &mut Xoshiro256Plus) -> (Bound<u8>, Bound<u8>) {
+    loop {
+        let range = (random_bound(rng), random_bound(rng));
+        match range {
+            (Bound::Excluded(a), Bound::Excluded(b)) if a == b => continue,
+            (Bound::Included(a) | Bound::Excluded(a), Bound::Included(b) | Bound::Excluded(b))
+                if a > b =>
+            {
+                continue
+            }
+            _ => return range,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use advanced_structures::lists::ConcurrentSkipList;
+use crossbeam_epoch as epoch;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256Plus;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Value that counts its live copies, so a test can tell whether the list
+/// dropped everything it was given.
+struct Tracked {
+    value: usize,
+    live: &'static AtomicUsize,
+}
+
+impl Tracked {
+    fn new(value: usize, live: &'static AtomicUsize) -> Self {
+        live.fetch_add(1, Ordering::SeqCst);
+        Tracked { value, live }
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        Tracked::new(self.value, self.live)
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Load {
+    threads: usize,
+    ops: usize,
+    range: usize,
+}
+
+const SMALL: Load = Load {
+    threads: 4,
+    ops: if cfg!(miri) { 50 } else { 2_000 },
+    range: if cfg!(miri) { 16 } else { 100 },
+};
+
+const LONG: Load = Load {
+    threads: 8,
+    ops: 100_000,
+    range: 1_000,
+};
+
+/// Removed nodes are freed by whichever thread collects the garbage later,
+/// so keep pinning until every deferred drop has run.
+fn wait_for_drops(live: &AtomicUsize) {
+    for _ in 0..1_000_000 {
+        if live.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        epoch::pin().flush();
+    }
+    panic!("{} values were never dropped", live.load(Ordering::SeqCst));
+}
+
+/// All threads fight over the same keys. Only the totals can be checked:
+/// every successful insert minus every successful pop must be left in the list.
+fn contended(load: &Load, live: &'static AtomicUsize) {
+    let sl = ConcurrentSkipList::<usize, Tracked>::with_fraction(0.5);
+    let inserted = AtomicUsize::new(0);
+    let popped = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for t in 0..load.threads {
+            let (sl, inserted, popped) = (&sl, &inserted, &popped);
+            s.spawn(move || {
+                let mut rng = Xoshiro256Plus::seed_from_u64(t as u64);
+                for _ in 0..load.ops {
+                    let key = rng.gen_range(0..load.range);
+                    match rng.gen_range(0..4) {
+                        0 => {
+                            if sl.insert(key, Tracked::new(key * 2, live)) {
+                                inserted.fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+                        1 => {
+                            if sl.pop(&key).is_some() {
+                                popped.fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+                        2 => {
+                            // Only inserts still under way may be counted
+                            // on top of the keys in the list
+                            assert!(sl.len() <= load.range + load.threads, "length wrapped");
+                            if let Some(found) = sl.find(&key) {
+                                assert_eq!(found.value, key * 2, "found a foreign value");
+                            }
+                        }
+                        _ => {
+                            let keys: Vec<_> = sl.iter().map(|(key, _)| key).take(8).collect();
+                            assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let expected = inserted.load(Ordering::SeqCst) - popped.load(Ordering::SeqCst);
+    assert_eq!(
+        sl.len(),
+        expected,
+        "length drifted from the operation count"
+    );
+    let entries: Vec<_> = sl.iter().collect();
+    assert_eq!(
+        entries.len(),
+        expected,
+        "reachable keys differ from the length"
+    );
+    assert!(entries.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(entries.iter().all(|(key, found)| found.value == key * 2));
+
+    drop(entries);
+    drop(sl);
+    wait_for_drops(live);
+}
+
+/// Every thread owns the keys congruent to its index, so each one can check
+/// every single result against its own model while the others keep working.
+fn partitioned(load: &Load, live: &'static AtomicUsize) {
+    let sl = ConcurrentSkipList::<usize, Tracked>::with_fraction(0.5);
+
+    let models: Vec<BTreeSet<usize>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..load.threads)
+            .map(|t| {
+                let sl = &sl;
+                s.spawn(move || {
+                    let mut rng = Xoshiro256Plus::seed_from_u64(1000 + t as u64);
+                    let mut model = BTreeSet::new();
+
+                    for _ in 0..load.ops {
+                        let key = rng.gen_range(0..load.range) * load.threads + t;
+                        match rng.gen_range(0..3) {
+                            0 => assert_eq!(
+                                sl.insert(key, Tracked::new(key * 2, live)),
+                                model.insert(key)
+                            ),
+                            1 => assert_eq!(sl.pop(&key).is_some(), model.remove(&key)),
+                            _ => assert_eq!(
+                                sl.find(&key).map(|found| found.value),
+                                model.get(&key).map(|k| k * 2),
+                                "lookup disagrees with the model"
+                            ),
+                        }
+                    }
+
+                    model
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let expected: BTreeSet<usize> = models.into_iter().flatten().collect();
+    assert_eq!(sl.len(), expected.len(), "length drifted from the models");
+    assert!(sl
+        .iter()
+        .map(|(key, found)| (key, found.value))
+        .eq(expected.iter().map(|&key| (key, key * 2))));
+
+    drop(sl);
+    wait_for_drops(live);
+}
+
+#[test]
+#[cfg_attr(
+    miri,
+    ignore = "crossbeam-epoch breaks Stacked Borrows, run with \
+    MIRIFLAGS=\"-Zmiri-tree-borrows -Zmiri-ignore-leaks\" and --ignored"
+)]
+fn contended_operations_keep_len_and_contents() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    contended(&SMALL, &LIVE);
+}
+
+#[test]
+#[cfg_attr(
+    miri,
+    ignore = "crossbeam-epoch breaks Stacked Borrows, run with \
+    MIRIFLAGS=\"-Zmiri-tree-borrows -Zmiri-ignore-leaks\" and --ignored"
+)]
+fn partitioned_operations_match_models() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    partitioned(&SMALL, &LIVE);
+}
+
+#[test]
+fn default_levels_are_sized_for_a_capacity() {
+    let sl = ConcurrentSkipList::<usize, usize>::default();
+    assert_eq!(sl.max_level(), 32);
+    assert_eq!(
+        ConcurrentSkipList::<usize, usize>::with_fraction(0.25).max_level(),
+        16
+    );
+    assert_eq!(
+        ConcurrentSkipList::<usize, usize>::with_capacity(1000, 0.5).max_level(),
+        10
+    );
+}
+
+#[test]
+#[should_panic(expected = "fraction must be between 0 and 1")]
+fn fraction_of_one_is_rejected() {
+    ConcurrentSkipList::<usize, usize>::new(1.0, 8);
+}
+
+#[test]
+#[should_panic(expected = "max_level must be at most")]
+fn huge_max_level_is_rejected() {
+    ConcurrentSkipList::<usize, usize>::new(0.5, usize::MAX);
+}
+
+#[test]
+#[ignore = "takes a while, run with --ignored"]
+fn stress() {
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    contended(&LONG, &LIVE);
+    partitioned(&LONG, &LIVE);
+}
@@ -0,0 +1,84 @@
+use advanced_structures::lists::shared_skip_list::Snapshot;
+use advanced_structures::lists::skip_list::Cursor;
+use advanced_structures::lists::{MultiList, Shared, SharedSkipList, SkipList, SkipSet};
+use advanced_structures::sparse_matrix::SparseMatrix;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::thread;
+
+/// Tells at compile time whether `T` is `Send` or `Sync` without failing the
+/// build when it isn't: the inherent constants shadow the trait defaults only
+/// when their bounds hold.
+struct Probe<T>(PhantomData<T>);
+
+trait Fallback {
+    const SEND: bool = false;
+    const SYNC: bool = false;
+}
+
+impl<T> Fallback for Probe<T> {}
+
+impl<T: Send> Probe<T> {
+    const SEND: bool = true;
+}
+
+impl<T: Sync> Probe<T> {
+    const SYNC: bool = true;
+}
+
+#[test]
+fn pointer_based_collections_are_send_and_sync() {
+    const {
+        assert!(Probe::<SkipList<String, Vec<u8>>>::SEND);
+        assert!(Probe::<SkipList<String, Vec<u8>>>::SYNC);
+        assert!(Probe::<SkipList<u32, Cell<u32>>>::SEND);
+        assert!(Probe::<SkipSet<u64>>::SEND);
+        assert!(Probe::<SkipSet<u64>>::SYNC);
+        assert!(Probe::<Snapshot<u64, String>>::SEND);
+        assert!(Probe::<Snapshot<u64, String>>::SYNC);
+        assert!(Probe::<SparseMatrix<f64>>::SEND);
+        assert!(Probe::<SparseMatrix<f64>>::SYNC);
+    }
+}
+
+#[test]
+fn sparse_matrix_moves_to_worker() {
+    let matrix = SparseMatrix::from_2d_vec(vec![vec![1, 0, 2], vec![0, 3, 0]]);
+    let sum = thread::spawn(move || {
+        (0..matrix.rows())
+            .flat_map(|r| matrix.row_iter(r))
+            .sum::<i32>()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(sum, 6);
+}
+
+#[test]
+fn snapshot_is_read_while_list_changes() {
+    let mut list = SharedSkipList::with_capacity_in(1000, 0.5, Shared::new());
+    list.extend((0..1000).map(|k| (k, k)));
+    let snapshot = list.snapshot();
+
+    let reader = {
+        let snapshot = snapshot.clone();
+        thread::spawn(move || snapshot.iter().map(|(_, v)| *v as u64).sum::<u64>())
+    };
+    list.retain(|k, _| k % 2 == 0);
+
+    assert_eq!(reader.join().unwrap(), 999 * 1000 / 2);
+    assert_eq!(snapshot.len(), 1000);
+    assert_eq!(list.len(), 500);
+}
+
+#[test]
+fn non_thread_safe_types_are_rejected() {
+    const {
+        assert!(!Probe::<MultiList<i32>>::SEND);
+        assert!(!Probe::<MultiList<i32>>::SYNC);
+        assert!(!Probe::<SkipList<u32, Rc<u32>>>::SEND);
+        assert!(!Probe::<SkipList<u32, Cell<u32>>>::SYNC);
+        assert!(!Probe::<Cursor<'static, u32, Cell<u32>>>::SEND);
+    }
+}